@@ -0,0 +1,101 @@
+//! 任务进行期间循环播放的背景音：内置的白噪声、褐噪声由代码实时生成，
+//! 也可以指定本地音频文件循环播放。
+
+use rodio::{Decoder, Source};
+use serde::{Deserialize, Serialize};
+use std::{fs::File, io::BufReader, time::Duration};
+
+const SAMPLE_RATE: u32 = 48000;
+
+#[derive(Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AmbientSound {
+    #[default]
+    Off,
+    WhiteNoise,
+    /// 低频更多、更柔和的噪声，类似雨声
+    BrownNoise,
+    /// 本地音频文件的路径
+    File(String),
+}
+
+impl AmbientSound {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AmbientSound::Off => "无",
+            AmbientSound::WhiteNoise => "白噪声",
+            AmbientSound::BrownNoise => "褐噪声",
+            AmbientSound::File(_) => "音频文件",
+        }
+    }
+
+    pub fn is_off(&self) -> bool {
+        *self == AmbientSound::Off
+    }
+}
+
+/// 打开可以无限循环播放的音源，关闭或文件无法解码时返回错误说明
+pub fn open(sound: &AmbientSound) -> Result<Box<dyn Source<Item = f32> + Send>, String> {
+    match sound {
+        AmbientSound::Off => Err("未设置背景音".to_string()),
+        AmbientSound::WhiteNoise => Ok(Box::new(Noise::new(false))),
+        AmbientSound::BrownNoise => Ok(Box::new(Noise::new(true))),
+        AmbientSound::File(path) => {
+            let file = File::open(path).map_err(|e| format!("无法打开 {}: {}", path, e))?;
+            let decoder = Decoder::new_looped(BufReader::new(file)).map_err(|e| format!("无法解码 {}: {}", path, e))?;
+            Ok(Box::new(decoder.convert_samples()))
+        }
+    }
+}
+
+/// 用 xorshift 生成的单声道噪声，`brown` 时对白噪声做积分得到褐噪声
+struct Noise {
+    state: u32,
+    brown: bool,
+    last: f32,
+}
+
+impl Noise {
+    fn new(brown: bool) -> Self {
+        Self {
+            state: 0x9E37_79B9,
+            brown,
+            last: 0.0,
+        }
+    }
+}
+
+impl Iterator for Noise {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        let white = self.state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+        if self.brown {
+            // 带泄漏的积分，防止直流漂移
+            self.last = (self.last * 0.98 + white * 0.1).clamp(-1.0, 1.0);
+            Some(self.last * 0.5)
+        } else {
+            Some(white * 0.1)
+        }
+    }
+}
+
+impl Source for Noise {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
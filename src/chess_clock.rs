@@ -0,0 +1,134 @@
+//! 双方交替走时的棋钟。只保存时间状态，时刻由调用方传入，界面和提醒在 main.rs 中。
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 每步结束后的加时方式
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Increment {
+    #[default]
+    None,
+    /// 每步固定加时
+    Fischer,
+    /// 返还本步用时，但不超过加时上限
+    Bronstein,
+}
+
+impl Increment {
+    pub const ALL: [Increment; 3] = [Increment::None, Increment::Fischer, Increment::Bronstein];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Increment::None => "不加时",
+            Increment::Fischer => "费舍尔",
+            Increment::Bronstein => "布朗斯坦",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChessSettings {
+    pub base_minutes: u64,
+    pub increment_secs: u64,
+    pub increment: Increment,
+}
+
+impl Default for ChessSettings {
+    fn default() -> Self {
+        Self {
+            base_minutes: 5,
+            increment_secs: 3,
+            increment: Increment::Fischer,
+        }
+    }
+}
+
+pub struct ChessClock {
+    settings: ChessSettings,
+    /// 两方在当前这步开始前的剩余时间
+    banked: [Duration; 2],
+    /// 正在走时的一方及其这步的开始时刻
+    turn: Option<(usize, Instant)>,
+    /// 暂停时轮到的一方
+    paused: Option<usize>,
+    /// 超时的一方，超时后棋钟停止
+    pub flagged: Option<usize>,
+}
+
+impl ChessClock {
+    pub fn new(settings: ChessSettings) -> Self {
+        let base = Duration::from_secs(settings.base_minutes * 60);
+        Self {
+            settings,
+            banked: [base; 2],
+            turn: None,
+            paused: None,
+            flagged: None,
+        }
+    }
+
+    pub fn remaining(&self, side: usize, now: Instant) -> Duration {
+        match self.turn {
+            Some((active, start)) if active == side => self.banked[side].saturating_sub(now - start),
+            _ => self.banked[side],
+        }
+    }
+
+    pub fn active(&self) -> Option<usize> {
+        self.turn.map(|(side, _)| side)
+    }
+
+    /// `side` 一方按下自己的钟：结束这一方的这步并开始对方计时；尚未开始时直接让对方开始
+    pub fn press(&mut self, side: usize, now: Instant) {
+        if self.flagged.is_some() || self.paused.is_some() {
+            return;
+        }
+        match self.turn {
+            Some((active, start)) if active == side => {
+                let used = now - start;
+                let bonus = Duration::from_secs(self.settings.increment_secs);
+                let bonus = match self.settings.increment {
+                    Increment::None => Duration::ZERO,
+                    Increment::Fischer => bonus,
+                    Increment::Bronstein => bonus.min(used),
+                };
+                self.banked[side] = self.banked[side].saturating_sub(used) + bonus;
+            }
+            // 按的是没在走时的一方，不算一步
+            Some(_) => return,
+            None => {}
+        }
+        self.turn = Some((1 - side, now));
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// 暂停时把当前一方这步的用时记入，继续后这一方接着走时
+    pub fn pause(&mut self, now: Instant) {
+        if let Some((side, _)) = self.turn {
+            self.banked[side] = self.remaining(side, now);
+            self.turn = None;
+            self.paused = Some(side);
+        }
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        if let Some(side) = self.paused.take() {
+            self.turn = Some((side, now));
+        }
+    }
+
+    /// 检查是否有一方用完时间，刚超时时返回这一方
+    pub fn tick(&mut self, now: Instant) -> Option<usize> {
+        let (side, _) = self.turn?;
+        if self.remaining(side, now).is_zero() {
+            self.banked[side] = Duration::ZERO;
+            self.turn = None;
+            self.flagged = Some(side);
+            return Some(side);
+        }
+        None
+    }
+}
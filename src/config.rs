@@ -0,0 +1,91 @@
+//! 部署用的启动配置 `config.toml`，与运行时保存的 JSON 数据分开。
+//! 其中的默认值只在还没有保存数据时生效；数据文件路径始终以配置为准。
+//! 命令行参数写法见 [`parse_args`]，同名设置覆盖配置文件。界面只有中文，没有语言设置。
+
+use super::{QuickPreset, ThemeMode};
+use serde::Deserialize;
+use std::{fs, io::ErrorKind};
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// 运行时数据文件的路径
+    pub data_path: Option<String>,
+    /// 提醒音量，0.0 到 1.0
+    pub volume: Option<f32>,
+    pub muted: Option<bool>,
+    /// "Light"、"Dark" 或 "Auto"
+    pub theme: Option<ThemeMode>,
+    /// 快捷预设，写法同 `[[presets]]` 下的 `name` 与 `input`，可选 `alert_sound` 与 `color`（RGBA 数组）
+    pub presets: Option<Vec<QuickPreset>>,
+}
+
+impl Config {
+    /// 用 `other` 中设置了的项覆盖自己，用于让命令行参数优先于配置文件
+    pub fn override_with(self, other: Config) -> Config {
+        Config {
+            data_path: other.data_path.or(self.data_path),
+            volume: other.volume.or(self.volume),
+            muted: other.muted.or(self.muted),
+            theme: other.theme.or(self.theme),
+            presets: other.presets.or(self.presets),
+        }
+    }
+}
+
+/// 命令行参数：配置文件路径和覆盖配置文件的设置
+pub struct Args {
+    pub config_path: String,
+    pub overrides: Config,
+}
+
+/// 解析命令行参数（不含程序名）：`--config <路径>`、`--data-path <路径>`、`--volume <0.0-1.0>`、
+/// `--theme <Light|Dark|Auto>`、`--muted`；遇到未知参数或缺少、无效的值时返回错误说明
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        config_path: CONFIG_PATH.to_string(),
+        overrides: Config::default(),
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("参数 {} 缺少值", arg));
+        match arg.as_str() {
+            "--config" => parsed.config_path = value()?,
+            "--data-path" => parsed.overrides.data_path = Some(value()?),
+            "--volume" => {
+                let text = value()?;
+                let volume = text
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|v| (0.0..=1.0).contains(v))
+                    .ok_or_else(|| format!("--volume 应为 0.0 到 1.0，而不是 {}", text))?;
+                parsed.overrides.volume = Some(volume);
+            }
+            "--theme" => {
+                parsed.overrides.theme = Some(match value()?.to_ascii_lowercase().as_str() {
+                    "light" => ThemeMode::Light,
+                    "dark" => ThemeMode::Dark,
+                    "auto" => ThemeMode::Auto,
+                    other => return Err(format!("--theme 应为 Light、Dark 或 Auto，而不是 {}", other)),
+                });
+            }
+            "--muted" => parsed.overrides.muted = Some(true),
+            _ => return Err(format!("未知的命令行参数 {}", arg)),
+        }
+    }
+    Ok(parsed)
+}
+
+/// 读取配置文件；文件不存在时返回 `Ok(None)`，无法读取或格式错误时返回错误说明
+pub fn load(path: &str) -> Result<Option<Config>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("无法读取 {}: {}", path, e)),
+    };
+    toml::from_str(&text)
+        .map(Some)
+        .map_err(|e| format!("{} 格式错误: {}", path, e))
+}
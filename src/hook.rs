@@ -0,0 +1,49 @@
+//! 任务结束时的扩展动作。每种后端实现 `FinishHook`，收到的是结束任务序列化后的 JSON；
+//! 目前只有外部命令，以后的 WASM 或脚本后端按同样的接口接入即可。
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+pub trait FinishHook {
+    /// 启动动作后立即返回，不能阻塞界面；只报告启动阶段的错误
+    fn run(&self, task_json: &str) -> io::Result<()>;
+}
+
+/// 通过系统 shell 执行的命令，任务 JSON 写入其标准输入
+pub struct CommandHook {
+    pub command: String,
+}
+
+impl FinishHook for CommandHook {
+    fn run(&self, task_json: &str) -> io::Result<()> {
+        let mut command = if cfg!(windows) {
+            let mut c = Command::new("cmd");
+            c.arg("/C");
+            c
+        } else {
+            let mut c = Command::new("sh");
+            c.arg("-c");
+            c
+        };
+        let mut child = command
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // 写入标准输入并等待退出放到后台线程，命令卡住也不会影响计时
+        let mut stdin = child.stdin.take();
+        let json = task_json.to_string();
+        std::thread::spawn(move || {
+            if let Some(stdin) = &mut stdin {
+                let _ = stdin.write_all(json.as_bytes());
+            }
+            drop(stdin);
+            let _ = child.wait();
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+//! 系统空闲时间检测：返回距离用户最后一次键盘/鼠标操作的时长，
+//! 不支持的平台或检测失败时返回 `None`。
+
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+pub fn system_idle_time() -> Option<Duration> {
+    // X11 下依赖 xprintidle，输出为毫秒数
+    let output = std::process::Command::new("xprintidle").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let ms = String::from_utf8_lossy(&output.stdout).trim().parse::<u64>().ok()?;
+    Some(Duration::from_millis(ms))
+}
+
+#[cfg(target_os = "macos")]
+pub fn system_idle_time() -> Option<Duration> {
+    // ioreg 输出中的 HIDIdleTime 单位为纳秒
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let line = text.lines().find(|l| l.contains("\"HIDIdleTime\""))?;
+    let ns = line.rsplit('=').next()?.trim().parse::<u64>().ok()?;
+    Some(Duration::from_nanos(ns))
+}
+
+#[cfg(windows)]
+pub fn system_idle_time() -> Option<Duration> {
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    let mut info = LastInputInfo {
+        cb_size: std::mem::size_of::<LastInputInfo>() as u32,
+        dw_time: 0,
+    };
+    // SAFETY: info 是按 LASTINPUTINFO 布局初始化的结构，cb_size 已正确设置
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // SAFETY: GetTickCount 没有参数，也没有前置条件
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(now.wrapping_sub(info.dw_time) as u64))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn system_idle_time() -> Option<Duration> {
+    None
+}
@@ -1,19 +1,179 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
 use eframe::{egui, App, Frame};
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+use rodio::{
+    source::{SineWave, Zero},
+    Decoder, OutputStream, OutputStreamHandle, Sink, Source,
+};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{HashMap, HashSet},
     fs,
     io::Cursor,
-    path::Path,
-    time::{Duration, Instant},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use egui::{Color32, Rect, TextureOptions, RichText};
+#[cfg(feature = "background-url")]
+use std::{io::Read, sync::mpsc};
+
+mod ambient;
+mod chess_clock;
+mod config;
+mod hook;
+mod status_image;
+#[cfg(feature = "idle-detection")]
+mod idle;
+#[cfg(feature = "lan-sync")]
+mod sync;
+
+use egui::{Color32, TextureOptions};
 
 const CUSTOM_FONT_DATA: &[u8] = include_bytes!("方正小标宋简体.TTF");
 const ALARM_WAV: &[u8] = include_bytes!("alarm.wav");
 const BACKGROUND_IMAGE_PATH: &str = "background.png";
+const DEFAULT_APP_TITLE: &str = "Rust 多任务倒计时";
+/// 时长输入框“?”说明中列出的格式，修改 `parse_duration` 的语法时同步更新
+const DURATION_FORMAT_HELP: &[(&str, &str)] = &[
+    ("秒数", "90 → 1分30秒"),
+    ("分:秒", "1:30 → 1分30秒"),
+    ("时:分:秒", "1:02:03 → 1小时2分3秒"),
+    ("带单位", "1h30m → 1小时30分，单位为 h/m/s"),
+    ("加减", "5m+30s、1h-10m，从左到右计算"),
+];
+
+/// 任务列表与历史记录两个滚动区域的总高度及各自的最小高度
+const LIST_AREA_HEIGHT: f32 = 450.0;
+const LIST_AREA_MIN_HEIGHT: f32 = 60.0;
+/// 超过该时长的新任务需要再确认一次，防止多打了几位数
+const LONG_DURATION_WARN: Duration = Duration::from_secs(24 * 3600);
+/// 能输入的最长时长，更长的一律视为输入错误，避免后续的时长计算溢出
+const MAX_DURATION: Duration = Duration::from_secs(366 * 24 * 3600);
+/// 一次批量删除超过该条数的历史记录时需要确认
+const BULK_DELETE_CONFIRM: usize = 5;
+/// 同时保留的声音上限，超出时停止最早的一个，防止 Sink 因异常未被清理而越积越多
+const MAX_ACTIVE_SINKS: usize = 16;
+/// “常用”一行最多显示的数量
+const FREQUENT_LIMIT: usize = 5;
+/// 至少完成过这么多次才会出现在“常用”中
+const FREQUENT_MIN_COUNT: usize = 2;
+/// 剩余时间不超过该值时视为即将结束，可自动滚动到该任务
+const NEAR_FINISH: Duration = Duration::from_secs(10);
+/// 结束时记下的结果在任务备注中的行首
+const OUTCOME_PREFIX: &str = "结果: ";
+/// 导出的统计图宽度
+const STATS_SVG_WIDTH: f32 = 640.0;
+/// 导出的统计图用白底，文字固定为深色，不跟随界面的文字颜色
+const STATS_SVG_TEXT: &str = "#333333";
+/// 新添加任务的高亮持续时间
+const ADDED_HIGHLIGHT: Duration = Duration::from_millis(1200);
+const LEAD_FLASH: Duration = Duration::from_secs(3);
+/// 最多可以撤销的删除次数
+const UNDO_LIMIT: usize = 10;
+/// 删除后“撤销”提示的显示时间
+const UNDO_TOAST: Duration = Duration::from_secs(6);
+/// 棋钟左右两方的名称
+const CHESS_SIDES: [&str; 2] = ["白方", "黑方"];
+/// 重要任务结束提醒音相对普通提醒的增益
+const IMPORTANT_ALARM_GAIN: f32 = 1.6;
+/// 高优先级任务结束后未确认时，每隔这么久再响一次
+const HIGH_PRIORITY_REPEAT: Duration = Duration::from_secs(30);
+/// 可选的进度提醒百分比
+const MILESTONE_CHOICES: [u8; 4] = [25, 50, 75, 90];
+/// 背景文件夹中会被当作图片的扩展名
+const BACKGROUND_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "bmp", "webp"];
+#[cfg(feature = "background-url")]
+const BACKGROUND_CACHE_PATH: &str = "background_cache.png";
+#[cfg(feature = "background-url")]
+const BACKGROUND_DOWNLOAD_LIMIT: u64 = 32 * 1024 * 1024;
+
+/// 任务的创建来源
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum TaskSource {
+    /// 在添加表单中手动创建
+    #[default]
+    Manual,
+    /// 特别倒计时（午夜或纪念日）
+    Special,
+    /// 通过“再来一次”重新开始
+    Repeat,
+    /// 结束提醒被推迟后重新创建
+    Snooze,
+    /// 点击快捷预设按钮创建
+    Preset,
+    /// 连续倒计时中的一步
+    Chain,
+    /// 点击根据历史记录推荐的常用任务创建
+    Frequent,
+    /// 重复计时结束后自动安排的下一次
+    Recurring,
+    /// 复制进行中的任务
+    Duplicate,
+}
+
+impl TaskSource {
+    fn label(self) -> &'static str {
+        match self {
+            TaskSource::Manual => "手动",
+            TaskSource::Special => "特别倒计时",
+            TaskSource::Repeat => "再来一次",
+            TaskSource::Snooze => "稍后提醒",
+            TaskSource::Preset => "快捷预设",
+            TaskSource::Chain => "连续倒计时",
+            TaskSource::Frequent => "常用",
+            TaskSource::Recurring => "重复计时",
+            TaskSource::Duplicate => "复制",
+        }
+    }
+}
+
+/// 任务的优先级，与“重要”星标相互独立。高优先级排在重要任务之后、普通任务之前，
+/// 带彩色边框，结束提醒在确认前反复响起；低优先级排在最后
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    fn short(self) -> &'static str {
+        match self {
+            Priority::High => "高",
+            Priority::Normal => "中",
+            Priority::Low => "低",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Priority::High => "高优先级",
+            Priority::Normal => "普通",
+            Priority::Low => "低优先级",
+        }
+    }
+
+    /// 点击优先级按钮时依次切换
+    fn next(self) -> Self {
+        match self {
+            Priority::Normal => Priority::High,
+            Priority::High => Priority::Low,
+            Priority::Low => Priority::Normal,
+        }
+    }
+}
+
+/// 任务的计时方式
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum TaskKind {
+    /// 从设定时长倒数到零
+    #[default]
+    Countdown,
+    /// 从零开始正计时，手动停止，可以计次
+    Stopwatch,
+    /// 在指定的本地时间响铃，按墙钟判断，不受休眠或暂停影响
+    Alarm { at: DateTime<Local> },
+}
 
 #[derive(Clone, Serialize, Deserialize)]
 struct CountdownTask {
@@ -28,8 +188,78 @@ struct CountdownTask {
     #[serde(skip)]
     pause_start: Option<Instant>,
     elapsed_before_pause: Duration,
-    #[serde(skip)]
+    #[serde(default)]
     finished_at: Option<DateTime<Local>>,
+    #[serde(default = "default_acknowledged")]
+    acknowledged: bool, // 结束提醒是否已被确认，未确认的历史记录会在重启后补发提醒
+    #[serde(default)]
+    realerted: bool, // 重启后已补发过一次提醒，之后不再补发
+    #[serde(default)]
+    locked: bool, // 锁定后不能暂停或停止
+    #[serde(skip)]
+    fired_milestones: Vec<u8>, // 已发送过通知的进度百分比
+    #[serde(skip)]
+    halfway_chimed: bool, // 是否已越过一半（过半提示音只响一次）
+    #[serde(default)]
+    special: bool, // 倒数到午夜或纪念日的特别倒计时
+    #[serde(default)]
+    source: TaskSource,
+    #[serde(default)]
+    finish_text: String, // 自定义完成语，为空时使用默认提示
+    #[serde(default = "default_alert_enabled")]
+    alert_sound: bool, // 结束时播放提醒音
+    #[serde(default = "default_alert_enabled")]
+    alert_notify: bool, // 结束时发送系统通知
+    #[serde(default)]
+    chain: Option<ChainLink>, // 属于连续倒计时时，结束后自动开始下一步
+    #[serde(default)]
+    pomodoro: Option<PomodoroLink>, // 属于番茄钟时，结束后自动开始下一阶段
+    #[serde(default)]
+    recurring: Option<usize>, // 所属重复计时的编号，结束后按规则安排下一次
+    #[serde(default, alias = "lead_reminder_secs", deserialize_with = "deserialize_lead_reminders")]
+    lead_reminders: Vec<u64>, // 剩余这些秒数时各提前提醒一次，从大到小
+    #[serde(skip)]
+    lead_reminded: Vec<u64>, // 已经提醒过的提前量
+    #[serde(skip)]
+    lead_flash: Option<Instant>, // 最近一次提前提醒的时刻，卡片随之闪烁
+    #[serde(skip)]
+    near_finish_seen: bool, // 是否已因即将结束而自动滚动过
+    #[serde(default)]
+    ambient: ambient::AmbientSound, // 计时期间循环播放的背景音
+    #[serde(default)]
+    note: String, // 多行备注，创建时或进行中填写；结束时记下的结果以 `OUTCOME_PREFIX` 开头追加在末尾
+    #[serde(skip)]
+    frozen_display: Option<(Duration, f32)>, // 冻结显示时的剩余时间和进度快照，计时照常进行
+    #[serde(default)]
+    important: bool, // 重要任务置顶显示，结束提醒更响，且必须手动确认
+    #[serde(default)]
+    priority: Priority,
+    #[serde(skip)]
+    rung_at: Option<Instant>, // 高优先级任务最近一次响起结束提醒的时刻
+    #[serde(default)]
+    color: Option<[u8; 4]>, // 来自预设的卡片颜色
+    #[serde(default)]
+    alarm_pattern: Option<AlarmPattern>, // 来自预设的结束提醒音样式，None 时使用设置中的样式
+    #[serde(default)]
+    tags: Vec<String>, // 自由填写的分类标签，例如“工作”“厨房”
+    #[serde(default)]
+    kind: TaskKind,
+    #[serde(default)]
+    laps: Vec<Duration>, // 秒表每次计次时的累计用时
+    #[serde(default)]
+    count_overtime: bool, // 到时后继续计算超时，直到确认或删除
+    #[serde(default)]
+    overtime: Option<Duration>, // 确认或删除时记下的超时时长
+    #[serde(default)]
+    snoozed_from: Option<usize>, // 稍后提醒时为最初那个任务的编号
+    #[serde(default)]
+    snooze_count: u32, // 同一任务已被推迟的次数
+    #[serde(default)]
+    extended: Duration, // 运行中用“+N分”累计延长的时长，已计入 duration
+    #[serde(default)]
+    auto_remove: bool, // 结束后过一段时间自动确认并移出任务列表
+    #[serde(default)]
+    scheduled_start: Option<DateTime<Local>>, // 等待到这个时间才开始计时，等待期间处于暂停状态
 }
 
 impl CountdownTask {
@@ -45,6 +275,131 @@ impl CountdownTask {
             pause_start: None,
             elapsed_before_pause: Duration::ZERO,
             finished_at: None,
+            acknowledged: false,
+            realerted: false,
+            locked: false,
+            fired_milestones: Vec::new(),
+            halfway_chimed: false,
+            special: false,
+            source: TaskSource::Manual,
+            finish_text: String::new(),
+            alert_sound: true,
+            alert_notify: true,
+            chain: None,
+            pomodoro: None,
+            recurring: None,
+            lead_reminders: Vec::new(),
+            lead_reminded: Vec::new(),
+            lead_flash: None,
+            near_finish_seen: false,
+            ambient: ambient::AmbientSound::Off,
+            note: String::new(),
+            frozen_display: None,
+            important: false,
+            priority: Priority::Normal,
+            rung_at: None,
+            color: None,
+            alarm_pattern: None,
+            tags: Vec::new(),
+            kind: TaskKind::Countdown,
+            laps: Vec::new(),
+            count_overtime: false,
+            overtime: None,
+            snoozed_from: None,
+            snooze_count: 0,
+            extended: Duration::ZERO,
+            auto_remove: false,
+            scheduled_start: None,
+        }
+    }
+
+    /// 从零开始的秒表，设定时长为 0，停止时改为实际用时
+    fn stopwatch(id: usize, name: String) -> Self {
+        let mut task = Self::new(id, name, "秒表".to_string(), Duration::ZERO);
+        task.kind = TaskKind::Stopwatch;
+        task
+    }
+
+    /// 在 `at` 响铃的闹钟，设定时长为从现在到响铃的时间，只用于进度和统计
+    fn alarm(id: usize, name: String, at: DateTime<Local>) -> Self {
+        let duration = (at - Local::now()).to_std().unwrap_or(Duration::ZERO);
+        let mut task = Self::new(id, name, at.format("%Y-%m-%d %H:%M").to_string(), duration);
+        task.kind = TaskKind::Alarm { at };
+        task
+    }
+
+    /// 重新开始、推迟或导入时沿用原任务的个性化设置
+    fn inherit_options(&mut self, from: &CountdownTask) {
+        self.finish_text = from.finish_text.clone();
+        self.alert_sound = from.alert_sound;
+        self.alert_notify = from.alert_notify;
+        self.lead_reminders = from.lead_reminders.clone();
+        self.ambient = from.ambient.clone();
+        self.important = from.important;
+        self.priority = from.priority;
+        self.color = from.color;
+        self.alarm_pattern = from.alarm_pattern;
+        self.tags = from.tags.clone();
+        self.note = from.note_without_outcome();
+        self.count_overtime = from.count_overtime;
+        self.auto_remove = from.auto_remove;
+    }
+
+    /// 把结束时记下的结果追加到备注末尾
+    fn add_outcome(&mut self, outcome: &str) {
+        if !self.note.is_empty() {
+            self.note.push('\n');
+        }
+        self.note += OUTCOME_PREFIX;
+        self.note += outcome;
+    }
+
+    /// 备注中最近一次记下的结果
+    fn outcome(&self) -> Option<&str> {
+        self.note.lines().rev().find_map(|line| line.strip_prefix(OUTCOME_PREFIX))
+    }
+
+    /// 去掉结果行的备注，重新开始或复制任务时沿用
+    fn note_without_outcome(&self) -> String {
+        self.note
+            .lines()
+            .filter(|line| !line.starts_with(OUTCOME_PREFIX))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 原地延长设定时长，设定时长的文字同步改为新的总时长；闹钟则推迟响铃时间
+    fn extend(&mut self, by: Duration) {
+        self.duration = self.duration.saturating_add(by);
+        self.extended = self.extended.saturating_add(by);
+        if let TaskKind::Alarm { at } = self.kind {
+            let at = at + chrono::Duration::from_std(by).unwrap_or_default();
+            self.kind = TaskKind::Alarm { at };
+            self.input = at.format("%Y-%m-%d %H:%M").to_string();
+        } else {
+            self.input = format_hms(self.duration, true);
+        }
+        self.rearm_reminders();
+    }
+
+    /// 修改设定时长，已用时间（含 `elapsed_before_pause`）不变，剩余时间和进度随之重算；
+    /// 新时长即为设定值，之前的延长不再单独记录
+    fn set_duration(&mut self, duration: Duration, input: String) {
+        self.duration = duration;
+        self.input = input;
+        self.extended = Duration::ZERO;
+        self.rearm_reminders();
+    }
+
+    /// 时长变化后，让又落在前面的提前提醒、进度提醒和即将结束滚动重新生效
+    fn rearm_reminders(&mut self) {
+        let remaining = self.remaining();
+        self.lead_reminded.retain(|&secs| remaining <= Duration::from_secs(secs));
+        let progress = self.progress();
+        self.fired_milestones.retain(|&pct| progress * 100.0 >= pct as f32);
+        self.halfway_chimed = progress >= 0.5;
+        if remaining > NEAR_FINISH {
+            self.near_finish_seen = false;
         }
     }
 
@@ -61,6 +416,9 @@ impl CountdownTask {
     }
 
     fn remaining(&self) -> Duration {
+        if let TaskKind::Alarm { at } = self.kind {
+            return (at - Local::now()).to_std().unwrap_or(Duration::ZERO);
+        }
         let elapsed = self.elapsed();
         if elapsed >= self.duration {
             Duration::ZERO
@@ -69,381 +427,6802 @@ impl CountdownTask {
         }
     }
 
-    fn is_finished(&self) -> bool {
-        self.elapsed() >= self.duration
+    /// 到时之后又过去了多久，按墙钟计算，重启后补发的提醒也准确
+    fn overtime_now(&self) -> Duration {
+        self.finished_at
+            .and_then(|at| (Local::now() - at).to_std().ok())
+            .unwrap_or(Duration::ZERO)
     }
-}
-
-#[derive(Serialize, Deserialize, Default)]
-struct PersistentData {
-    history: Vec<CountdownTask>,
-    text_color: [u8; 4], // egui::Color32 RGBA
-}
 
-struct ClockApp {
-    tasks: Vec<CountdownTask>,
-    next_task_id: usize,
-    new_task_input: String,
-    new_task_name: String, // 新增任务名输入框内容
-    history: Vec<CountdownTask>,
-    show_finished_popup: Option<usize>,
+    /// 已到时、开启了超时计时且还没有确认，超时时长仍在增加
+    fn counting_overtime(&self) -> bool {
+        self.count_overtime && self.is_finished() && self.overtime.is_none()
+    }
 
-    background_texture: Option<egui::TextureHandle>,
-    text_color: Color32,
+    /// 任务列表中的分组：重要任务、高、普通、低优先级依次排列
+    fn list_group(&self) -> u8 {
+        match (self.important, self.priority) {
+            (true, _) => 0,
+            (false, Priority::High) => 1,
+            (false, Priority::Normal) => 2,
+            (false, Priority::Low) => 3,
+        }
+    }
 
-    _stream: OutputStream,
-    stream_handle: OutputStreamHandle,
-    active_sinks: Vec<Sink>,
-}
+    /// 尚未到时的闹钟和特别倒计时按墙钟计时，保存到数据文件，重启后恢复；重复计时会重新安排，不保存
+    fn is_saved_pending(&self) -> bool {
+        matches!(self.kind, TaskKind::Alarm { .. }) && self.finished_at.is_none() && self.recurring.is_none()
+    }
 
-impl Default for ClockApp {
-    fn default() -> Self {
-        let (_stream, stream_handle) =
-            OutputStream::try_default().expect("Failed to initialize audio output");
+    /// 秒表只能手动停止，永远不会自行结束
+    fn is_finished(&self) -> bool {
+        match self.kind {
+            TaskKind::Countdown => self.elapsed() >= self.duration,
+            TaskKind::Stopwatch => false,
+            TaskKind::Alarm { at } => Local::now() >= at,
+        }
+    }
 
-        Self {
-            tasks: Vec::new(),
-            next_task_id: 0,
-            new_task_input: String::new(),
-            new_task_name: String::new(),
-            history: Vec::new(),
-            show_finished_popup: None,
-            background_texture: None,
-            text_color: Color32::from_rgb(220, 220, 220),
-            _stream,
-            stream_handle,
-            active_sinks: Vec::new(),
+    /// 界面上显示的时间：倒计时为剩余时间，秒表为已用时间
+    fn shown_time(&self) -> Duration {
+        match self.kind {
+            TaskKind::Countdown | TaskKind::Alarm { .. } => self.remaining(),
+            TaskKind::Stopwatch => self.elapsed(),
         }
     }
-}
 
-impl ClockApp {
-    fn data_path() -> &'static str {
-        "countdown_data.json"
+    fn pause(&mut self) {
+        self.pause_for(Duration::ZERO);
     }
 
-    fn load_data(&mut self) {
-        if Path::new(Self::data_path()).exists() {
-            if let Ok(data) = fs::read_to_string(Self::data_path()) {
-                if let Ok(persist) = serde_json::from_str::<PersistentData>(&data) {
-                    self.history = persist.history;
-                    self.text_color = Color32::from_rgba_unmultiplied(
-                        persist.text_color[0],
-                        persist.text_color[1],
-                        persist.text_color[2],
-                        persist.text_color[3],
-                    );
-                    if let Some(max_id) = self.history.iter().map(|t| t.id).max() {
-                        self.next_task_id = max_id + 1;
-                    }
-                }
-            }
+    /// 暂停，并把暂停时刻往前推 `ago`（例如从系统开始空闲时算起）；闹钟按墙钟响铃，不能暂停
+    fn pause_for(&mut self, ago: Duration) {
+        if self.paused || matches!(self.kind, TaskKind::Alarm { .. }) {
+            return;
         }
+        self.elapsed_before_pause = self.elapsed().saturating_sub(ago);
+        self.paused = true;
+        self.pause_start = Some(Instant::now());
     }
 
-    fn save_data(&self) {
-        let persist = PersistentData {
-            history: self.history.clone(),
-            text_color: self.text_color.to_array(),
-        };
-        if let Ok(json) = serde_json::to_string_pretty(&persist) {
-            let _ = fs::write(Self::data_path(), json);
+    /// 改为等待到 `at` 再开始：已用时间清零并保持暂停
+    fn schedule_start(&mut self, at: DateTime<Local>) {
+        self.elapsed_before_pause = Duration::ZERO;
+        self.paused = true;
+        self.pause_start = Some(Instant::now());
+        self.scheduled_start = Some(at);
+    }
+
+    fn resume(&mut self) {
+        if !self.paused {
+            return;
         }
+        self.scheduled_start = None;
+        self.start = Some(Instant::now());
+        self.paused = false;
+        self.pause_start = None;
     }
 
-    fn parse_duration(input: &str) -> Option<Duration> {
-        let parts: Vec<&str> = input.trim().split(':').collect();
-        match parts.len() {
-            1 => parts[0].parse::<u64>().ok().map(Duration::from_secs),
-            2 => {
-                let mins = parts[0].parse::<u64>().ok()?;
-                let secs = parts[1].parse::<u64>().ok()?;
-                Some(Duration::from_secs(mins * 60 + secs))
-            }
-            3 => {
-                let hours = parts[0].parse::<u64>().ok()?;
-                let mins = parts[1].parse::<u64>().ok()?;
-                let secs = parts[2].parse::<u64>().ok()?;
-                Some(Duration::from_secs(hours * 3600 + mins * 60 + secs))
-            }
-            _ => None,
+    /// 秒表没有终点，进度始终为 0
+    fn progress(&self) -> f32 {
+        if self.kind == TaskKind::Stopwatch {
+            return 0.0;
         }
+        1.0 - self.remaining().as_secs_f32() / self.duration.as_secs_f32()
     }
 
-    fn play_alarm_sound(&mut self) {
-        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
-            let cursor = Cursor::new(ALARM_WAV);
-            if let Ok(source) = Decoder::new(cursor) {
-                sink.append(source);
-                self.active_sinks.push(sink);
-            }
+    /// 界面上显示的时间和进度，冻结显示时为冻结那一刻的值
+    fn displayed(&self) -> (Duration, f32) {
+        self.frozen_display.unwrap_or_else(|| (self.shown_time(), self.progress()))
+    }
+
+    /// 结束时在通知和弹窗中显示的文字
+    fn finish_message(&self) -> String {
+        if !self.finish_text.trim().is_empty() {
+            self.finish_text.trim().to_string()
+        } else if self.special {
+            format!("🎉 {} 到啦！", self.name)
+        } else {
+            format!(
+                "任务“{}”开始于 {} 的倒计时已结束",
+                self.name,
+                self.created_at.format("%Y-%m-%d %H:%M:%S")
+            )
         }
     }
 
-    fn show_notification(summary: &str, body: &str) {
-        let _ = notify_rust::Notification::new()
-            .summary(summary)
-            .body(body)
-            .show();
+    /// 从创建到结束的实际墙钟用时超出设定时长的部分（主要来自暂停）
+    fn overrun(&self) -> Option<Duration> {
+        let finished_at = self.finished_at?;
+        let actual = (finished_at - self.created_at).to_std().ok()?;
+        let extra = actual.checked_sub(self.duration)?;
+        // 结束检测按帧进行，忽略几秒内的误差
+        (extra >= Duration::from_secs(2)).then_some(extra)
     }
+}
 
-    fn load_background(&mut self, ctx: &egui::Context) {
-        if self.background_texture.is_some() {
-            return;
-        }
-        if Path::new(BACKGROUND_IMAGE_PATH).exists() {
-            if let Ok(img) = image::open(BACKGROUND_IMAGE_PATH) {
-                let size = [img.width() as usize, img.height() as usize];
-                let img = img.to_rgba8();
-                let pixels = img.as_flat_samples();
-                let color_image =
-                    egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
-                self.background_texture = Some(ctx.load_texture(
-                    "background",
-                    color_image,
-                    TextureOptions::LINEAR,
-                ));
+/// 解析闹钟时间：`HH:MM` 为今天的该时刻（已经过去则为明天），或完整的 `YYYY-MM-DD HH:MM`
+fn parse_alarm_time(input: &str, now: DateTime<Local>) -> Result<DateTime<Local>, String> {
+    let input = input.trim();
+    let naive = match NaiveTime::parse_from_str(input, "%H:%M") {
+        Ok(time) => {
+            let today = now.date_naive().and_time(time);
+            if today > now.naive_local() {
+                today
+            } else {
+                today + chrono::Duration::days(1)
             }
         }
+        Err(_) => NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M")
+            .map_err(|_| "时间格式应为 HH:MM 或 YYYY-MM-DD HH:MM".to_string())?,
+    };
+    let at = Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or("该时间在本地时区不存在")?;
+    if at <= now {
+        return Err("响铃时间已经过去".to_string());
     }
+    Ok(at)
 }
 
-impl App for ClockApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
-        use egui::*;
+/// 长时间倒计时的显示：满一天时为“N天 HH:MM:SS”，否则同 `format_hms`
+fn format_days_hms(d: Duration, compact: bool) -> String {
+    let days = d.as_secs() / 86400;
+    if days == 0 {
+        format_hms(d, compact)
+    } else {
+        format!("{}天 {}", days, format_hms(d - Duration::from_secs(days * 86400), false))
+    }
+}
 
-        self.active_sinks.retain(|sink| !sink.empty());
+/// 格式化为 HH:MM:SS；`compact` 时省略前导零，不足一小时显示为 M:SS
+fn format_hms(d: Duration, compact: bool) -> String {
+    let secs = d.as_secs();
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if !compact {
+        format!("{:02}:{:02}:{:02}", h, m, s)
+    } else if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
 
-        let mut style = (*ctx.style()).clone();
-        style.visuals.override_text_color = Some(self.text_color);
-        ctx.set_style(style);
+/// 进度对应的强调色：0 为绿色，1 为红色，中间经过黄色
+fn progress_accent(progress: f32, palette: &Palette) -> Color32 {
+    let p = progress.clamp(0.0, 1.0);
+    let [start, mid, end] = palette.progress;
+    if p < 0.5 {
+        lerp_color(start, mid, p * 2.0)
+    } else {
+        lerp_color(mid, end, (p - 0.5) * 2.0)
+    }
+}
 
-        self.load_background(ctx);
+fn lerp_color(a: Color32, b: Color32, t: f32) -> Color32 {
+    let mix = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+    Color32::from_rgb(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
+}
 
-        if let Some(texture) = &self.background_texture {
-            let painter = ctx.layer_painter(LayerId::background());
-            let rect = ctx.input(|i| i.screen_rect());
-            painter.image(texture.id(), rect, Rect::from_min_max(rect.min, rect.max), Color32::WHITE);
-        }
+/// 界面中表示状态的颜色，所有按状态着色的地方都从这里取色
+struct Palette {
+    error: Color32,   // 错误提示、断开连接
+    alert: Color32,   // 未确认徽标的底色
+    warning: Color32, // 超时徽标的底色
+    progress: [Color32; 3], // 进度渐变色在开始、一半、结束时的颜色
+    heat: [Color32; 4], // 热力图由浅到深四档
+    cue: bool, // 是否在错误提示前加符号，不只靠颜色区分
+}
 
-        CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.add_space(10.0);
-                ui.heading(
-                    RichText::new(Local::now().format("%H:%M:%S").to_string())
-                        .size(48.0)
-                        .color(self.text_color),
-                );
-                ui.add_space(10.0);
-            });
+const STANDARD_PALETTE: Palette = Palette {
+    error: Color32::from_rgb(220, 80, 80),
+    alert: Color32::from_rgb(200, 40, 40),
+    warning: Color32::from_rgb(240, 200, 80),
+    progress: [
+        Color32::from_rgb(60, 180, 60),
+        Color32::from_rgb(220, 180, 60),
+        Color32::from_rgb(220, 60, 60),
+    ],
+    heat: [
+        Color32::from_rgb(40, 105, 80),
+        Color32::from_rgb(40, 150, 90),
+        Color32::from_rgb(40, 195, 100),
+        Color32::from_rgb(40, 240, 110),
+    ],
+    cue: false,
+};
 
-            ui.separator();
+/// 色盲友好配色：取自 Okabe-Ito 色板，用蓝—橙和明暗区分，不依赖红绿
+const COLORBLIND_PALETTE: Palette = Palette {
+    error: Color32::from_rgb(230, 120, 20),
+    alert: Color32::from_rgb(213, 94, 0),
+    warning: Color32::from_rgb(240, 228, 66),
+    progress: [
+        Color32::from_rgb(86, 180, 233),
+        Color32::from_rgb(230, 159, 0),
+        Color32::from_rgb(213, 94, 0),
+    ],
+    heat: [
+        Color32::from_rgb(20, 60, 110),
+        Color32::from_rgb(0, 95, 160),
+        Color32::from_rgb(40, 140, 210),
+        Color32::from_rgb(120, 200, 250),
+    ],
+    cue: true,
+};
 
-            ui.horizontal(|ui| {
-                ui.label("文字颜色:");
-                let mut color = {
-                    let [r, g, b, _a] = self.text_color.to_array();
-                    [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0]
-                };
-                if ui.color_edit_button_rgb(&mut color).changed() {
-                    self.text_color = Color32::from_rgb(
-                        (color[0] * 255.0) as u8,
-                        (color[1] * 255.0) as u8,
-                        (color[2] * 255.0) as u8,
-                    );
-                    self.save_data();
+/// 按配色显示错误提示，色盲友好配色下另加“⚠”
+fn error_label(ui: &mut egui::Ui, palette: &Palette, text: impl ToString) -> egui::Response {
+    let text = if palette.cue {
+        format!("⚠ {}", text.to_string())
+    } else {
+        text.to_string()
+    };
+    ui.colored_label(palette.error, text)
+}
+
+/// 可选颜色的编辑按钮：未设置时显示“颜色”按钮，设置后显示取色器和“无色”
+fn optional_color_edit(ui: &mut egui::Ui, color: &mut Option<[u8; 4]>, hover: &str) {
+    match color {
+        Some(c) => {
+            let mut rgba = color_from_array(*c);
+            if ui.color_edit_button_srgba(&mut rgba).changed() {
+                *c = rgba.to_array();
+            }
+            if ui.small_button("无色").clicked() {
+                *color = None;
+            }
+        }
+        None => {
+            if ui.small_button("颜色").on_hover_text(hover).clicked() {
+                *color = Some(Color32::from_rgb(90, 170, 90).to_array());
+            }
+        }
+    }
+}
+
+/// 背景音选择框，选择音频文件时在旁边显示路径输入框
+fn ambient_picker(ui: &mut egui::Ui, id_source: &str, value: &mut ambient::AmbientSound) {
+    let choices = [
+        ambient::AmbientSound::Off,
+        ambient::AmbientSound::WhiteNoise,
+        ambient::AmbientSound::BrownNoise,
+        ambient::AmbientSound::File(String::new()),
+    ];
+    egui::ComboBox::from_id_source(id_source)
+        .selected_text(value.label())
+        .show_ui(ui, |ui| {
+            for choice in choices {
+                let selected = std::mem::discriminant(value) == std::mem::discriminant(&choice);
+                if ui.selectable_label(selected, choice.label()).clicked() && !selected {
+                    *value = choice;
                 }
-            });
+            }
+        })
+        .response
+        .on_hover_text("计时期间循环播放，暂停时一起暂停，结束时停止");
+    if let ambient::AmbientSound::File(path) = value {
+        ui.add(egui::TextEdit::singleline(path).hint_text("音频文件路径").desired_width(140.0));
+    }
+}
 
-            ui.separator();
+/// 解析逗号（含全角）或空格分隔的标签，去掉空项和重复项，保持输入顺序
+fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split([',', '，', '、', ' ']).map(str::trim).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
 
-            // 改为垂直布局，避免按钮被挤出窗口
-            ui.group(|ui| {
-                ui.label("任务名:");
-                ui.text_edit_singleline(&mut self.new_task_name);
-                ui.add_space(4.0);
+/// 标签的颜色：按标签文字的哈希取色相，同一标签每次得到相同的颜色
+fn tag_color(tag: &str) -> Color32 {
+    // FNV-1a，不依赖标准库哈希的随机种子
+    let hash = tag
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    let hue = (hash % 360) as f32 / 360.0;
+    egui::ecolor::Hsva::new(hue, 0.55, 0.85, 1.0).into()
+}
 
-                ui.label("倒计时 (秒或 HH:MM:SS):");
-                ui.text_edit_singleline(&mut self.new_task_input);
-                ui.add_space(4.0);
+/// 这些任务上出现过的全部标签，按名称排序
+fn collect_tags<'a>(tasks: impl IntoIterator<Item = &'a CountdownTask>) -> Vec<String> {
+    let mut tags: Vec<String> = tasks.into_iter().flat_map(|t| t.tags.iter().cloned()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
 
-                if ui.button("添加").clicked() {
-                    if let Some(dur) = Self::parse_duration(&self.new_task_input) {
-                        if dur.as_secs() > 0 {
-                            let id = self.next_task_id;
-                            self.next_task_id += 1;
-                            let name = if self.new_task_name.trim().is_empty() {
-                                format!("任务#{}", id)
-                            } else {
-                                self.new_task_name.trim().to_string()
-                            };
-                            self.tasks.push(CountdownTask::new(
-                                id,
-                                name,
-                                self.new_task_input.clone(),
-                                dur,
-                            ));
-                            self.new_task_input.clear();
-                            self.new_task_name.clear();
-                        }
-                    }
-                }
-            });
+/// 按标签筛选的按钮行，兼作标签颜色图例；再次点击已选标签取消筛选
+fn tag_filter_bar(ui: &mut egui::Ui, tags: &[String], filter: &mut Option<String>) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label("标签:");
+        if ui.selectable_label(filter.is_none(), "全部").clicked() {
+            *filter = None;
+        }
+        for tag in tags {
+            let selected = filter.as_deref() == Some(tag.as_str());
+            let text = egui::RichText::new(format!("● {}", tag)).color(tag_color(tag));
+            if ui.selectable_label(selected, text).clicked() {
+                *filter = if selected { None } else { Some(tag.clone()) };
+            }
+        }
+    });
+}
 
-            ui.separator();
+fn color_from_array(c: [u8; 4]) -> Color32 {
+    Color32::from_rgba_unmultiplied(c[0], c[1], c[2], c[3])
+}
 
-            let mut just_finished_tasks = Vec::new();
+/// 把每帧的回车状态变成一次添加动作：按住不放时的自动重复不会重复添加，松开后才能再次触发
+#[derive(Default)]
+struct EnterGuard {
+    latched: bool,
+}
 
-            ui.push_id("countdown_tasks", |ui| {
-                ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
-                    let mut remove_ids = Vec::new();
+impl EnterGuard {
+    /// 每帧调用一次；`pressed` 为本帧有回车按下事件（含自动重复），`held` 为回车仍被按住
+    fn fire(&mut self, pressed: bool, held: bool) -> bool {
+        if pressed && !self.latched {
+            self.latched = true;
+            return true;
+        }
+        if !pressed && !held {
+            self.latched = false;
+        }
+        false
+    }
+}
 
-                    for task in &mut self.tasks {
-                        if task.is_finished() && task.finished_at.is_none() {
-                            task.finished_at = Some(Local::now());
-                            just_finished_tasks.push(task.clone());
-                        }
+/// 分钟列表的设置文字，例如“1,5,10”
+fn join_minutes(minutes: &[u64]) -> String {
+    minutes.iter().map(|m| m.to_string()).collect::<Vec<_>>().join(",")
+}
 
-                        ui.group(|ui| {
-                            ui.vertical(|ui| {
-                                ui.label(
-                                    RichText::new(format!("任务名: {}", task.name)).strong(),
-                                );
-                                ui.label(RichText::new(
-                                    format!("开始时间: {}", task.created_at.format("%Y-%m-%d %H:%M:%S")),
-                                ));
-                                ui.label(format!("设定时长: {}", task.input));
+/// 适合朗读的时长文字，例如“3分20秒”“1小时5分”
+fn format_duration_words(d: Duration) -> String {
+    let secs = d.as_secs();
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    let mut text = String::new();
+    if h > 0 {
+        text.push_str(&format!("{}小时", h));
+    }
+    if m > 0 {
+        text.push_str(&format!("{}分", m));
+    }
+    if s > 0 || text.is_empty() {
+        text.push_str(&format!("{}秒", s));
+    }
+    text
+}
 
-                                ui.horizontal(|ui| {
-                                    let remain = task.remaining();
-                                    ui.label(format!(
-                                        "剩余时间: {:02}:{:02}:{:02}",
-                                        remain.as_secs() / 3600,
-                                        (remain.as_secs() / 60) % 60,
-                                        remain.as_secs() % 60
-                                    ));
-                                    let progress = 1.0 - remain.as_secs_f32() / task.duration.as_secs_f32();
-                                    ui.add(ProgressBar::new(progress).show_percentage());
-                                });
+/// 标题下方剩余时间汇总的计算方式
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum RemainingSummary {
+    /// 所有任务剩余时间之和
+    Sum,
+    /// 最长的剩余时间，即全部任务完成还需多久
+    #[default]
+    Max,
+}
 
-                                ui.horizontal(|ui| {
-                                    if task.is_finished() {
-                                        if ui.button("删除").clicked() {
-                                            remove_ids.push(task.id);
-                                        }
-                                    } else {
-                                        if task.paused {
-                                            if ui.button("继续").clicked() {
-                                                if let Some(pause_start) = task.pause_start {
-                                                    let paused_dur = pause_start.elapsed();
-                                                    task.elapsed_before_pause += paused_dur;
-                                                    task.paused = false;
-                                                    task.pause_start = None;
-                                                }
-                                            }
-                                        } else if ui.button("暂停").clicked() {
-                                            task.paused = true;
-                                            task.pause_start = Some(Instant::now());
-                                        }
+/// 背景图片铺满窗口的方式
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum BackgroundFit {
+    /// 拉伸到窗口大小，可能变形
+    #[default]
+    Stretch,
+    /// 保持比例铺满窗口，多出的部分裁掉
+    Cover,
+    /// 保持比例完整显示，可能留边
+    Contain,
+}
 
-                                        if ui.button("停止").clicked() {
-                                            remove_ids.push(task.id);
-                                        }
-                                    }
-                                });
-                            });
-                        });
+impl BackgroundFit {
+    const ALL: [BackgroundFit; 3] = [BackgroundFit::Stretch, BackgroundFit::Cover, BackgroundFit::Contain];
 
-                        ui.add_space(10.0);
-                    }
+    fn label(self) -> &'static str {
+        match self {
+            BackgroundFit::Stretch => "拉伸",
+            BackgroundFit::Cover => "填充",
+            BackgroundFit::Contain => "适应",
+        }
+    }
 
-                    self.tasks.retain(|t| !remove_ids.contains(&t.id));
+    fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&f| f == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
 
-                    for task in just_finished_tasks {
-                        self.play_alarm_sound();
-                        Self::show_notification(
-                            "倒计时结束",
-                            &format!("任务“{}”开始于 {} 的倒计时已结束", task.name, task.created_at.format("%Y-%m-%d %H:%M:%S")),
-                        );
-                        self.history.push(task.clone());
+    /// 返回在 `screen` 中绘制的区域和对应的纹理坐标
+    fn layout(self, screen: egui::Rect, image_size: egui::Vec2) -> (egui::Rect, egui::Rect) {
+        let full_uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        if image_size.x <= 0.0 || image_size.y <= 0.0 || screen.height() <= 0.0 {
+            return (screen, full_uv);
+        }
+        let image_aspect = image_size.x / image_size.y;
+        let screen_aspect = screen.width() / screen.height();
+        match self {
+            BackgroundFit::Stretch => (screen, full_uv),
+            BackgroundFit::Cover => {
+                let uv_size = if image_aspect > screen_aspect {
+                    egui::vec2(screen_aspect / image_aspect, 1.0)
+                } else {
+                    egui::vec2(1.0, image_aspect / screen_aspect)
+                };
+                (screen, egui::Rect::from_center_size(egui::pos2(0.5, 0.5), uv_size))
+            }
+            BackgroundFit::Contain => {
+                let size = if image_aspect > screen_aspect {
+                    egui::vec2(screen.width(), screen.width() / image_aspect)
+                } else {
+                    egui::vec2(screen.height() * image_aspect, screen.height())
+                };
+                (egui::Rect::from_center_size(screen.center(), size), full_uv)
+            }
+        }
+    }
+}
+
+/// 界面配色；自动模式按本地时间在白天用浅色、夜间用深色
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum ThemeMode {
+    Light,
+    #[default]
+    Dark,
+    Auto,
+}
+
+impl ThemeMode {
+    const ALL: [ThemeMode; 3] = [ThemeMode::Light, ThemeMode::Dark, ThemeMode::Auto];
+
+    fn label(self) -> &'static str {
+        match self {
+            ThemeMode::Light => "浅色",
+            ThemeMode::Dark => "深色",
+            ThemeMode::Auto => "自动",
+        }
+    }
+}
+
+/// 进行中任务列表的位置
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum TaskLayout {
+    /// 与时钟、添加表单一起放在中间
+    #[default]
+    Center,
+    /// 放在右侧可调宽度的侧栏中
+    Side,
+}
+
+/// 局域网同步中本机的角色
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum SyncMode {
+    #[default]
+    Off,
+    /// 广播本机的任务
+    Host,
+    /// 只读显示主机的任务
+    Mirror,
+}
+
+/// 结束提醒音的播放方式：响几次、每次之间隔多久、每次是否渐强
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct AlarmPattern {
+    repeat: u32,
+    interval_secs: f32,
+    fade_in_secs: f32,
+}
+
+impl Default for AlarmPattern {
+    fn default() -> Self {
+        ALARM_PATTERNS[0].1
+    }
+}
+
+/// 内置的提醒音样式，第一个为默认值
+const ALARM_PATTERNS: [(&str, AlarmPattern); 3] = [
+    ("单次", AlarmPattern { repeat: 1, interval_secs: 0.0, fade_in_secs: 0.0 }),
+    ("三连响", AlarmPattern { repeat: 3, interval_secs: 2.0, fade_in_secs: 0.0 }),
+    ("渐强三次", AlarmPattern { repeat: 3, interval_secs: 2.0, fade_in_secs: 1.5 }),
+];
+
+/// 正在播放的提醒音及其开始时间
+struct ActiveAlarm {
+    sink: Sink,
+    started: Instant,
+}
+
+/// 添加区域中一键开始的快捷预设，顺序即按钮顺序
+#[derive(Clone, Serialize, Deserialize)]
+struct QuickPreset {
+    name: String,
+    input: String, // 与添加表单相同的时长写法
+    #[serde(default = "default_preset_sound")]
+    alert_sound: bool, // 结束时是否响铃
+    #[serde(default)]
+    color: Option<[u8; 4]>, // 按钮和任务卡片的颜色
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    start_sound: bool, // 开始时响一声开始提示音
+    #[serde(default)]
+    alarm_pattern: Option<AlarmPattern>, // 结束提醒音样式，None 时跟随设置
+    #[serde(default)]
+    ambient: ambient::AmbientSound,
+}
+
+/// 连续倒计时中的一步
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct ChainStep {
+    name: String,
+    input: String, // 与添加表单相同的时长写法
+    #[serde(default)]
+    color: Option<[u8; 4]>, // 进行中时任务卡片和进度条的颜色
+}
+
+/// 按顺序自动衔接的一组倒计时，例如烹饪步骤或训练循环
+#[derive(Clone, Serialize, Deserialize)]
+struct CountdownChain {
+    name: String,
+    steps: Vec<ChainStep>,
+    #[serde(default = "default_chain_rounds")]
+    rounds: u32, // 全部步骤重复的轮数，例如间歇训练的 8 组
+    #[serde(default)]
+    quiet_transitions: bool, // 步骤之间只响切换提示音，不弹窗、不发通知
+}
+
+fn default_chain_rounds() -> u32 {
+    1
+}
+
+impl Default for CountdownChain {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            steps: Vec::new(),
+            rounds: default_chain_rounds(),
+            quiet_transitions: false,
+        }
+    }
+}
+
+/// 任务在连续倒计时中的位置；开始时复制全部步骤，运行中修改连续倒计时不影响已开始的
+#[derive(Clone, Serialize, Deserialize)]
+struct ChainLink {
+    chain: CountdownChain,
+    index: usize,
+    #[serde(default)]
+    round: u32, // 从 0 开始的当前轮次
+    #[serde(default)]
+    skipped: Vec<(u32, usize)>, // 在计划列表中取消的（轮次, 步骤），轮到时直接跳过
+}
+
+impl ChainLink {
+    fn new(chain: CountdownChain) -> Self {
+        Self {
+            chain,
+            index: 0,
+            round: 0,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// 当前步骤之后还会进行的（轮次, 步骤），按先后顺序，不含已取消的
+    fn upcoming(&self) -> impl Iterator<Item = (u32, usize)> + '_ {
+        let steps = self.chain.steps.len();
+        (self.round..self.chain.rounds)
+            .flat_map(move |round| (0..steps).map(move |index| (round, index)))
+            .filter(|&pos| pos > (self.round, self.index) && !self.skipped.contains(&pos))
+    }
+
+    /// 下一步的位置，最后一轮的最后一步之后为 None
+    fn next(&self) -> Option<ChainLink> {
+        self.upcoming().next().map(|(round, index)| ChainLink {
+            chain: self.chain.clone(),
+            index,
+            round,
+            skipped: self.skipped.clone(),
+        })
+    }
+
+    fn step(&self) -> Option<&ChainStep> {
+        self.chain.steps.get(self.index)
+    }
+}
+
+/// 番茄钟各阶段的时长和一轮的专注次数
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct PomodoroSettings {
+    work_minutes: u64,
+    break_minutes: u64,
+    long_break_minutes: u64, // 一轮最后一个专注之后的长休息，0 表示不休息直接结束
+    cycles: u32,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            break_minutes: 5,
+            long_break_minutes: 15,
+            cycles: 4,
+        }
+    }
+}
+
+/// 任务在番茄钟中的阶段；开始时复制设置，运行中修改设置不影响已开始的一轮
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct PomodoroLink {
+    settings: PomodoroSettings,
+    cycle: u32, // 第几个专注，从 1 开始
+    on_break: bool,
+}
+
+impl PomodoroLink {
+    /// 本阶段结束后的下一阶段，一轮结束时为 None。休息时长为 0 时跳过这次休息
+    fn next(self) -> Option<PomodoroLink> {
+        let last = self.cycle >= self.settings.cycles;
+        if !self.on_break {
+            let rest = if last { self.settings.long_break_minutes } else { self.settings.break_minutes };
+            if rest > 0 {
+                return Some(PomodoroLink { on_break: true, ..self });
+            }
+        }
+        (!last).then_some(PomodoroLink {
+            cycle: self.cycle + 1,
+            on_break: false,
+            ..self
+        })
+    }
+
+    fn minutes(&self) -> u64 {
+        match (self.on_break, self.cycle >= self.settings.cycles) {
+            (false, _) => self.settings.work_minutes,
+            (true, false) => self.settings.break_minutes,
+            (true, true) => self.settings.long_break_minutes,
+        }
+    }
+}
+
+/// 重复计时的规则
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum RepeatRule {
+    /// 每隔固定分钟数倒计时一次
+    Every { minutes: u64 },
+    /// 每天在这个时刻响铃
+    Daily { time: NaiveTime },
+    /// 周一到周五在这个时刻响铃
+    Weekdays { time: NaiveTime },
+}
+
+impl RepeatRule {
+    fn describe(&self) -> String {
+        match self {
+            RepeatRule::Every { minutes } => format!("每 {} 分钟", minutes),
+            RepeatRule::Daily { time } => format!("每天 {}", time.format("%H:%M")),
+            RepeatRule::Weekdays { time } => format!("工作日 {}", time.format("%H:%M")),
+        }
+    }
+
+    /// 按时刻响铃的规则在 `now` 之后的下一次响铃时间；按间隔的规则返回 None
+    fn next_after(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let (time, weekdays_only) = match *self {
+            RepeatRule::Every { .. } => return None,
+            RepeatRule::Daily { time } => (time, false),
+            RepeatRule::Weekdays { time } => (time, true),
+        };
+        (0..8)
+            .map(|days| now.date_naive() + chrono::Duration::days(days))
+            .filter(|date| !weekdays_only || date.weekday().num_days_from_monday() < 5)
+            .filter_map(|date| Local.from_local_datetime(&date.and_time(time)).earliest())
+            .find(|at| *at > now)
+    }
+}
+
+/// 保存在数据文件中的重复计时，重启后自动安排下一次
+#[derive(Clone, Serialize, Deserialize)]
+struct RecurringTimer {
+    id: usize,
+    name: String,
+    rule: RepeatRule,
+}
+
+/// “计划”列表中尚未开始的计时
+struct PlannedItem {
+    name: String,
+    /// 预计开始时间，闹钟和重复计时为下一次响铃时间；前面的任务暂停时无法确定
+    starts_at: Option<DateTime<Local>>,
+    cancel: PlannedCancel,
+}
+
+/// 取消计划项时需要修改的位置，新的计划来源在这里加一种
+#[derive(Clone, Copy)]
+enum PlannedCancel {
+    /// 进行中任务所属连续倒计时第 `round` 轮的第 `step` 步
+    ChainStep { task_id: usize, round: u32, step: usize },
+    /// 等待定时开始的任务
+    ScheduledTask { task_id: usize },
+    /// 尚未响铃的闹钟
+    PendingAlarm { task_id: usize },
+    /// 重复计时的下一次，取消即跳过这一次并安排再下一次
+    RecurringNext { task_id: usize },
+}
+
+/// 任务列表中的分组标题，只用于整理，不计时
+#[derive(Clone, Serialize, Deserialize)]
+struct TaskHeader {
+    title: String,
+    position: usize, // 显示在第几个任务之前，等于任务数时显示在末尾；保存时只数会保存的任务
+}
+
+/// 对分组标题的操作，在遍历任务列表之后统一执行
+enum HeaderAction {
+    Remove(usize),
+    MoveUp(usize),
+    MoveDown(usize),
+}
+
+/// 历史记录中反复完成的任务名和时长组合
+struct FrequentTask {
+    name: String,
+    input: String,
+    duration: Duration,
+    count: usize,
+}
+
+/// 一次可以撤销的删除，记下被删除的条目及其原来的下标（从小到大）
+enum UndoEntry {
+    /// 删除的任务、删除时的下一个任务编号（停止重复计时后安排的下一次编号不小于它），
+    /// 以及删除前的分组标题：删除会把分组末尾的位置和下一个标题挤到一起，撤销时按原样放回
+    Tasks(Vec<(usize, CountdownTask)>, usize, Vec<TaskHeader>),
+    History(Vec<(usize, CountdownTask)>),
+}
+
+impl UndoEntry {
+    fn describe(&self) -> String {
+        let (removed, what) = match self {
+            UndoEntry::Tasks(removed, _, _) => (removed, "任务"),
+            UndoEntry::History(removed) => (removed, "历史记录"),
+        };
+        match removed.as_slice() {
+            [(_, task)] => format!("已删除{}“{}”", what, task.name),
+            _ => format!("已删除 {} 条{}", removed.len(), what),
+        }
+    }
+}
+
+/// “编辑任务”窗口中的任务名和设定时长
+struct TaskEdit {
+    id: usize,
+    name: String,
+    input: String,
+    tags: String,
+    error: Option<String>,
+}
+
+/// 预设编辑器中正在修改的预设
+struct PresetEdit {
+    index: usize,
+    name: String,
+    input: String,
+    alert_sound: bool,
+    color: Option<[u8; 4]>,
+    tags: String,
+    start_sound: bool,
+    alarm_pattern: Option<AlarmPattern>,
+    ambient: ambient::AmbientSound,
+}
+
+fn default_acknowledged() -> bool {
+    true
+}
+
+/// 旧数据只有一个提前量（0 表示不提醒），新数据是提前量列表
+fn deserialize_lead_reminders<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Lead {
+        One(u64),
+        Many(Vec<u64>),
+    }
+    Ok(match Lead::deserialize(deserializer)? {
+        Lead::One(0) => Vec::new(),
+        Lead::One(secs) => vec![secs],
+        Lead::Many(list) => list,
+    })
+}
+
+fn default_show_background() -> bool {
+    true
+}
+
+fn default_alert_enabled() -> bool {
+    true
+}
+
+fn default_realert_window_minutes() -> u64 {
+    30
+}
+
+fn default_alarm_max_secs() -> u64 {
+    120
+}
+
+fn default_idle_threshold_secs() -> u64 {
+    300
+}
+
+fn default_snooze_minutes() -> u64 {
+    5
+}
+
+fn default_auto_remove_secs() -> u64 {
+    10
+}
+
+fn default_alarm_volume() -> f32 {
+    1.0
+}
+
+fn default_list_split() -> f32 {
+    300.0 / LIST_AREA_HEIGHT
+}
+
+fn default_side_panel_width() -> f32 {
+    360.0
+}
+
+fn default_sync_bind() -> String {
+    "0.0.0.0:47800".to_string()
+}
+
+fn default_sync_target() -> String {
+    "255.255.255.255:47800".to_string()
+}
+
+fn default_extend_minutes() -> Vec<u64> {
+    vec![1, 5, 10]
+}
+
+fn default_theme_day_start_hour() -> u32 {
+    7
+}
+
+fn default_theme_night_start_hour() -> u32 {
+    19
+}
+
+fn default_status_image_interval_secs() -> u64 {
+    1
+}
+
+fn default_presets() -> Vec<QuickPreset> {
+    [("泡面", "3:00"), ("休息", "5:00"), ("番茄钟", "25:00")]
+        .into_iter()
+        .map(|(name, input)| QuickPreset {
+            name: name.to_string(),
+            input: input.to_string(),
+            alert_sound: true,
+            color: None,
+            tags: Vec::new(),
+            start_sound: false,
+            alarm_pattern: None,
+            ambient: ambient::AmbientSound::Off,
+        })
+        .collect()
+}
+
+fn default_preset_sound() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct PersistentData {
+    history: Vec<CountdownTask>,
+    text_color: [u8; 4], // egui::Color32 RGBA
+    background_path: Option<String>, // 从 URL 下载并缓存到本地的背景图片
+    background_dir: String, // 背景图片文件夹，设置后优先于单张背景
+    background_random: bool, // 每次启动从文件夹中随机挑选，否则取文件名排序的第一张
+    #[serde(default = "default_show_background")]
+    show_background: bool, // 关闭后不绘制背景图片，也不保留纹理
+    theme_mode: ThemeMode,
+    #[serde(default = "default_theme_day_start_hour")]
+    theme_day_start_hour: u32, // 自动模式下从该小时起使用浅色
+    #[serde(default = "default_theme_night_start_hour")]
+    theme_night_start_hour: u32, // 自动模式下从该小时起使用深色
+    background_fit: BackgroundFit,
+    overlay_mode: bool, // 直播叠加层：无边框、置顶，只显示进行中的计时
+    overlay_background: [u8; 4], // 叠加层背景 RGBA，alpha 为 0 时透明
+    status_image_path: String, // 定时写出的计时状态 PNG，留空不写
+    status_text_enabled: bool, // 把最快结束的任务剩余时间写入文本文件，供状态栏脚本读取
+    status_text_path: String,
+    #[serde(default = "default_status_image_interval_secs")]
+    status_image_interval_secs: u64,
+    keep_running_on_close: bool,
+    #[serde(default = "default_alarm_max_secs")]
+    alarm_max_secs: u64, // 提醒最长时间，0 表示不限
+    alarm_pattern: AlarmPattern,
+    #[serde(default = "default_extend_minutes")]
+    extend_minutes: Vec<u64>, // 进行中任务上“+N分”按钮的分钟数
+    auto_names: Vec<String>, // 任务名留空时依次使用的名字，为空时用“任务#编号”
+    auto_name_index: usize, // 下一个要用的名字在 auto_names 中的位置
+    focus_goal_minutes: u64, // 每日专注目标，0 表示不设
+    focus_goal_reached_on: Option<NaiveDate>, // 最近一次达成目标的日期，避免同一天重复庆祝
+    app_title: String, // 自定义窗口标题，为空时使用默认标题
+    milestones: Vec<u8>, // 进度达到这些百分比时发送通知
+    special_target: String, // 特别倒计时的目标时间，为空表示下一个午夜
+    history_limit: usize, // 历史保留数量，0 表示不限
+    idle_pause: bool, // 系统空闲时自动暂停
+    #[serde(default = "default_idle_threshold_secs")]
+    idle_threshold_secs: u64,
+    compact_time: bool, // 省略时间中的前导零
+    clock_minutes_only: bool, // 时钟只显示到分钟
+    show_date: bool, // 在时钟下方显示日期和星期
+    progress_color: bool, // 任务卡片的强调色随进度由绿渐变到红
+    colorblind: bool, // 状态颜色改用色盲友好配色
+    finish_hook_enabled: bool, // 安全开关：关闭时不运行任何结束动作
+    finish_hook_command: String, // 任务结束时执行的命令，任务 JSON 从标准输入传入
+    pinned_window_pos: Option<[f32; 2]>, // 固定显示器时窗口的位置
+    pinned_monitor_size: Option<[f32; 2]>, // 固定时所在显示器的尺寸，用于判断显示器是否还在
+    pinned_monitor_scale: Option<f32>, // 固定时所在显示器的缩放比例，与尺寸一起区分显示器
+    #[serde(default = "default_list_split")]
+    list_split: f32, // 任务列表占两个滚动区域总高度的比例
+    task_layout: TaskLayout,
+    #[serde(default = "default_side_panel_width")]
+    side_panel_width: f32, // 侧栏布局时任务列表的宽度
+    fullscreen: bool, // 全屏时钟模式，下次启动时恢复
+    remaining_summary: RemainingSummary,
+    #[serde(default = "default_alarm_volume")]
+    alarm_volume: f32, // 提醒音量 0.0 ~ 1.0
+    muted: bool,
+    #[serde(default = "default_snooze_minutes")]
+    snooze_minutes: u64, // 稍后提醒的间隔
+    #[serde(default = "default_auto_remove_secs")]
+    auto_remove_secs: u64, // 设为自动移除的任务结束后保留多少秒
+    #[serde(default = "default_presets")]
+    presets: Vec<QuickPreset>,
+    chains: Vec<CountdownChain>,
+    pomodoro: PomodoroSettings,
+    recurring: Vec<RecurringTimer>,
+    chess: chess_clock::ChessSettings,
+    pending_alarms: Vec<CountdownTask>, // 尚未到时的闹钟和特别倒计时，按墙钟计时，重启后恢复
+    task_headers: Vec<TaskHeader>,
+    #[serde(default = "default_realert_window_minutes")]
+    realert_window_minutes: u64, // 重启后补发最近多少分钟内未确认的提醒，0 表示不补发
+    halfway_chime: bool, // 任务进度过半时播放轻提示音
+    hour_chime: bool, // 整点报时
+    add_feedback: bool, // 添加任务时轻响一声并短暂高亮新任务
+    scroll_on_add: bool, // 添加任务后把任务列表滚动到新任务
+    scroll_on_near_finish: bool, // 任务即将结束时把任务列表滚动到该任务
+    half_hour_chime: bool, // 半点也报时
+    sync_mode: SyncMode,
+    #[serde(default = "default_sync_bind")]
+    sync_bind: String, // 镜像端监听的地址
+    #[serde(default = "default_sync_target")]
+    sync_target: String, // 主机发送快照的目标，可以是广播地址
+}
+
+struct ClockApp {
+    tasks: Vec<CountdownTask>,
+    next_task_id: usize,
+    new_task_input: String,
+    new_task_name: String, // 新增任务名输入框内容
+    new_task_finish_text: String,
+    new_task_note: String,
+    new_task_sound: bool,
+    new_task_notify: bool,
+    new_task_overtime: bool,
+    new_task_auto_remove: bool,
+    new_task_lead_input: String, // 提前提醒的时长，可用逗号分隔多项，留空表示不提醒
+    new_task_tags: String,       // 逗号或空格分隔的标签，添加后保留，方便连续添加同类任务
+    task_tag_filter: Option<String>,    // 任务列表只显示带这个标签的任务
+    history_tag_filter: Option<String>, // 历史记录只显示带这个标签的记录
+    new_task_start_input: String, // 定时开始的时间，留空表示立即开始
+    add_enter_guard: EnterGuard, // 时长输入框中按回车添加任务
+    new_task_ambient: ambient::AmbientSound,
+    ambient_sinks: HashMap<usize, Sink>, // 任务编号 → 正在循环播放的背景音
+    ambient_failed: HashSet<usize>, // 背景音无法打开的任务，不再每帧重试
+    ambient_status: Option<String>,
+    history: Vec<CountdownTask>,
+    show_finished_popup: Option<usize>,
+    finish_note: String, // 结束提醒中正在输入的结果备注
+    finish_note_id: Option<usize>, // finish_note 属于哪个任务
+    last_finished: Option<CountdownTask>, // 最近结束的普通任务，用于“再来一次”
+    add_error: Option<String>, // 添加表单的校验提示
+    confirm_long_add: bool,    // 时长超过 LONG_DURATION_WARN，等待再次确认
+    task_file_path: String, // 单个任务导入/导出的文件路径
+    task_file_status: Option<String>,
+    history_merge_path: String, // 要合并历史记录的其他数据文件
+    history_merge_status: Option<String>,
+
+    background_texture: Option<egui::TextureHandle>,
+    background_path: Option<String>,
+    background_dir: String,
+    background_random: bool,
+    background_dir_choice: Option<PathBuf>, // 本次运行从文件夹中选中的图片
+    background_dir_status: Option<String>,
+    background_fit: BackgroundFit,
+    background_fit_toast: Option<Instant>, // 用快捷键切换铺放方式后短暂显示提示
+    show_background: bool,
+    theme_mode: ThemeMode,
+    theme_day_start_hour: u32,
+    theme_night_start_hour: u32,
+    overlay_mode: bool,
+    overlay_background: Color32,
+    applied_overlay: Option<bool>, // 已应用到窗口的叠加层状态
+    overlay_drag_requested: bool,
+    status_image_path: String,
+    status_image_interval_secs: u64,
+    last_status_image: Option<Instant>,
+    status_image_status: Option<String>,
+    status_text_enabled: bool,
+    status_text_path: String,
+    last_status_text: Option<String>, // 最近一次写入的内容，None 表示文件已删除
+    status_text_dirty: bool, // 启动或改了设置后，无论内容是否变化都要同步一次文件
+    status_text_written: Option<String>, // 当前留在磁盘上的状态文件路径，关闭或改路径后删除
+    status_text_status: Option<String>,
+    #[cfg(feature = "background-url")]
+    background_url_input: String,
+    #[cfg(feature = "background-url")]
+    background_download: Option<mpsc::Receiver<Result<Vec<u8>, String>>>,
+    #[cfg(feature = "background-url")]
+    background_status: Option<String>,
+    text_color: Color32,
+    keep_running_on_close: bool,
+    minimize_requested: bool,
+    quit_requested: bool,
+    stats_days: i64,
+    repeat_day_request: Option<NaiveDate>, // 等待确认“重复这一天”的日期
+    stats_export_path: String,
+    stats_export_status: Option<String>,
+    app_title: String,
+    data_path: String, // 运行时数据文件，可由 config.toml 指定
+    config_warning: Option<String>, // config.toml 或命令行参数无效时的提示
+    applied_title: String,
+    milestones: Vec<u8>,
+    special_target: String,
+    special_status: Option<String>,
+    confirm_stop_all: bool, // “全部停止”等待再次确认
+    alarm_name: String,
+    alarm_input: String,
+    alarm_status: Option<String>,
+    history_limit: usize,
+    idle_pause: bool,
+    idle_threshold_secs: u64,
+    compact_time: bool,
+    clock_minutes_only: bool,
+    show_date: bool,
+    progress_color: bool,
+    colorblind: bool,
+    finish_hook_enabled: bool,
+    finish_hook_command: String,
+    finish_hook_status: Option<String>,
+    pinned_window_pos: Option<[f32; 2]>,
+    pinned_monitor_size: Option<[f32; 2]>,
+    pinned_monitor_scale: Option<f32>,
+    monitor_checked: bool,
+    center_on_primary: bool, // 已移到主显示器左上角，下一帧在主显示器上居中
+    monitor_status: Option<String>,
+    pin_monitor_requested: bool,
+    list_split: f32,
+    task_layout: TaskLayout,
+    side_panel_width: f32,
+    fullscreen: bool,
+    remaining_summary: RemainingSummary,
+    alarm_volume: f32,
+    muted: bool,
+    snooze_minutes: u64,
+    auto_remove_secs: u64,
+    presets: Vec<QuickPreset>,
+    realert_window_minutes: u64,
+    halfway_chime: bool,
+    hour_chime: bool,
+    half_hour_chime: bool,
+    last_hour_chime: Option<(chrono::NaiveDate, u32, u32)>, // 最近一次报时的日期、时、分，避免同一分钟内重复
+    add_feedback: bool,
+    added_highlight: Option<(usize, Instant)>, // 刚添加的任务编号和添加时刻
+    scroll_on_add: bool,
+    scroll_on_near_finish: bool,
+    scroll_to_task: Option<usize>, // 下一帧要滚动到的任务，只在添加或即将结束时设置一次，不干扰手动滚动
+    sync_mode: SyncMode,
+    sync_bind: String,
+    sync_target: String,
+    task_headers: Vec<TaskHeader>,
+    header_input: String,
+    history_select_mode: bool, // 历史记录多选模式
+    history_selected: HashSet<usize>, // 多选模式下勾选的历史记录编号
+    confirm_history_delete: bool,
+    header_edit: Option<(usize, String)>, // 正在重命名的分组标题下标和新标题
+    frequent: Vec<FrequentTask>, // 根据历史记录推荐的常用任务
+    frequent_key: Option<(usize, Option<usize>)>, // 计算 frequent 时历史记录的条数和最后一条的编号
+    preset_drag: Option<usize>, // 编辑器中正在拖动的预设下标
+    preset_edit: Option<PresetEdit>,
+    task_edit: Option<TaskEdit>, // 正在编辑的进行中任务
+    task_drag: Option<usize>,    // 任务列表中正在拖动的任务编号
+    undo_stack: Vec<UndoEntry>,  // 最近的删除，最多 UNDO_LIMIT 次
+    undo_toast: Option<Instant>, // 最近一次删除的时刻，期间显示“撤销”提示
+    preset_status: Option<String>,
+    preset_overwrite: Option<QuickPreset>, // 等待确认覆盖的同名预设
+    chains: Vec<CountdownChain>,
+    chain_draft: CountdownChain, // 编辑中的连续倒计时
+    chain_status: Option<String>,
+    chain_overwrite: Option<CountdownChain>, // 等待确认覆盖的同名连续倒计时
+    pomodoro: PomodoroSettings,
+    chess_settings: chess_clock::ChessSettings,
+    chess: Option<chess_clock::ChessClock>, // 进行中的棋钟
+    recurring: Vec<RecurringTimer>,
+    recurring_name: String,
+    recurring_rule: RepeatRule, // 添加表单中的规则，时刻类规则的时间取自 recurring_time
+    recurring_time: String,
+    recurring_status: Option<String>,
+    global_freeze: Option<DateTime<Local>>, // 全局冻结开始的时刻，冻结期间所有计时停止
+    freeze_paused_ids: Vec<usize>, // 因全局冻结而暂停、解除时需要继续的任务
+    freeze_clock: bool, // 冻结期间时钟也停在冻结时刻
+    #[cfg(feature = "idle-detection")]
+    idle_supported: bool,
+    #[cfg(feature = "idle-detection")]
+    last_idle_poll: Option<Instant>,
+    #[cfg(feature = "idle-detection")]
+    idle_paused_ids: Vec<usize>, // 因空闲而自动暂停、回来时需要继续的任务
+    #[cfg(feature = "lan-sync")]
+    sync_host: Option<sync::Host>,
+    #[cfg(feature = "lan-sync")]
+    sync_mirror: Option<sync::Mirror>,
+    #[cfg(feature = "lan-sync")]
+    sync_status: Option<String>,
+
+    _stream: OutputStream,
+    stream_handle: OutputStreamHandle,
+    active_sinks: Vec<ActiveAlarm>,
+    alarm_max_secs: u64,
+    alarm_pattern: AlarmPattern,
+    alarm_pattern_custom: bool, // 显示自定义样式的编辑项
+    extend_minutes: Vec<u64>,
+    extend_minutes_input: String,
+    auto_names: Vec<String>,
+    auto_name_index: usize,
+    auto_names_input: String,
+    focus_goal_minutes: u64,
+    focus_goal_reached_on: Option<NaiveDate>,
+}
+
+impl Default for ClockApp {
+    fn default() -> Self {
+        let (_stream, stream_handle) =
+            OutputStream::try_default().expect("Failed to initialize audio output");
+
+        Self {
+            tasks: Vec::new(),
+            next_task_id: 0,
+            new_task_input: String::new(),
+            new_task_name: String::new(),
+            new_task_finish_text: String::new(),
+            new_task_note: String::new(),
+            new_task_sound: true,
+            new_task_notify: true,
+            new_task_overtime: false,
+            new_task_auto_remove: false,
+            new_task_lead_input: String::new(),
+            new_task_tags: String::new(),
+            task_tag_filter: None,
+            history_tag_filter: None,
+            new_task_start_input: String::new(),
+            add_enter_guard: EnterGuard::default(),
+            new_task_ambient: ambient::AmbientSound::Off,
+            ambient_sinks: HashMap::new(),
+            ambient_failed: HashSet::new(),
+            ambient_status: None,
+            history: Vec::new(),
+            show_finished_popup: None,
+            finish_note: String::new(),
+            finish_note_id: None,
+            last_finished: None,
+            add_error: None,
+            confirm_long_add: false,
+            task_file_path: String::new(),
+            task_file_status: None,
+            history_merge_path: String::new(),
+            history_merge_status: None,
+            background_texture: None,
+            background_path: None,
+            background_dir: String::new(),
+            background_random: false,
+            background_dir_choice: None,
+            background_dir_status: None,
+            background_fit: BackgroundFit::default(),
+            background_fit_toast: None,
+            show_background: default_show_background(),
+            theme_mode: ThemeMode::default(),
+            theme_day_start_hour: default_theme_day_start_hour(),
+            theme_night_start_hour: default_theme_night_start_hour(),
+            overlay_mode: false,
+            overlay_background: Color32::TRANSPARENT,
+            applied_overlay: None,
+            overlay_drag_requested: false,
+            status_image_path: String::new(),
+            status_image_interval_secs: default_status_image_interval_secs(),
+            last_status_image: None,
+            status_image_status: None,
+            status_text_enabled: false,
+            status_text_path: String::new(),
+            last_status_text: None,
+            status_text_dirty: true,
+            status_text_written: None,
+            status_text_status: None,
+            #[cfg(feature = "background-url")]
+            background_url_input: String::new(),
+            #[cfg(feature = "background-url")]
+            background_download: None,
+            #[cfg(feature = "background-url")]
+            background_status: None,
+            text_color: Color32::from_rgb(220, 220, 220),
+            keep_running_on_close: false,
+            minimize_requested: false,
+            quit_requested: false,
+            stats_days: 7,
+            repeat_day_request: None,
+            stats_export_path: "stats.svg".to_string(),
+            stats_export_status: None,
+            app_title: String::new(),
+            data_path: "countdown_data.json".to_string(),
+            config_warning: None,
+            applied_title: DEFAULT_APP_TITLE.to_string(),
+            milestones: Vec::new(),
+            special_target: String::new(),
+            special_status: None,
+            confirm_stop_all: false,
+            alarm_name: String::new(),
+            alarm_input: String::new(),
+            alarm_status: None,
+            history_limit: 0,
+            idle_pause: false,
+            idle_threshold_secs: default_idle_threshold_secs(),
+            compact_time: false,
+            clock_minutes_only: false,
+            show_date: false,
+            progress_color: false,
+            colorblind: false,
+            finish_hook_enabled: false,
+            finish_hook_command: String::new(),
+            finish_hook_status: None,
+            pinned_window_pos: None,
+            pinned_monitor_size: None,
+            pinned_monitor_scale: None,
+            center_on_primary: false,
+            monitor_checked: false,
+            monitor_status: None,
+            pin_monitor_requested: false,
+            list_split: default_list_split(),
+            task_layout: TaskLayout::default(),
+            side_panel_width: default_side_panel_width(),
+            fullscreen: false,
+            remaining_summary: RemainingSummary::default(),
+            alarm_volume: default_alarm_volume(),
+            muted: false,
+            snooze_minutes: default_snooze_minutes(),
+            auto_remove_secs: default_auto_remove_secs(),
+            presets: default_presets(),
+            realert_window_minutes: default_realert_window_minutes(),
+            halfway_chime: false,
+            hour_chime: false,
+            half_hour_chime: false,
+            last_hour_chime: None,
+            add_feedback: false,
+            added_highlight: None,
+            scroll_on_add: false,
+            scroll_on_near_finish: false,
+            scroll_to_task: None,
+            sync_mode: SyncMode::default(),
+            sync_bind: default_sync_bind(),
+            sync_target: default_sync_target(),
+            preset_drag: None,
+            preset_edit: None,
+            task_edit: None,
+            task_drag: None,
+            undo_stack: Vec::new(),
+            undo_toast: None,
+            frequent: Vec::new(),
+            frequent_key: None,
+            task_headers: Vec::new(),
+            header_input: String::new(),
+            history_select_mode: false,
+            history_selected: HashSet::new(),
+            confirm_history_delete: false,
+            header_edit: None,
+            preset_status: None,
+            preset_overwrite: None,
+            chains: Vec::new(),
+            chain_draft: CountdownChain::default(),
+            chain_status: None,
+            chain_overwrite: None,
+            pomodoro: PomodoroSettings::default(),
+            chess_settings: chess_clock::ChessSettings::default(),
+            chess: None,
+            recurring: Vec::new(),
+            recurring_name: String::new(),
+            recurring_rule: RepeatRule::Every { minutes: 30 },
+            recurring_time: String::new(),
+            recurring_status: None,
+            #[cfg(feature = "idle-detection")]
+            idle_supported: idle::system_idle_time().is_some(),
+            global_freeze: None,
+            freeze_paused_ids: Vec::new(),
+            freeze_clock: false,
+            #[cfg(feature = "idle-detection")]
+            last_idle_poll: None,
+            #[cfg(feature = "idle-detection")]
+            idle_paused_ids: Vec::new(),
+            #[cfg(feature = "lan-sync")]
+            sync_host: None,
+            #[cfg(feature = "lan-sync")]
+            sync_mirror: None,
+            #[cfg(feature = "lan-sync")]
+            sync_status: None,
+            _stream,
+            stream_handle,
+            active_sinks: Vec::new(),
+            alarm_max_secs: default_alarm_max_secs(),
+            alarm_pattern: AlarmPattern::default(),
+            alarm_pattern_custom: false,
+            extend_minutes: default_extend_minutes(),
+            auto_names: Vec::new(),
+            auto_name_index: 0,
+            auto_names_input: String::new(),
+            extend_minutes_input: join_minutes(&default_extend_minutes()),
+            focus_goal_minutes: 0,
+            focus_goal_reached_on: None,
+        }
+    }
+}
+
+impl ClockApp {
+    fn data_path(&self) -> &str {
+        &self.data_path
+    }
+
+    /// 应用 config.toml 中的默认值，需在 `load_data` 之前调用，已保存的数据会覆盖这些默认值
+    fn apply_config(&mut self, config: config::Config) {
+        if let Some(path) = config.data_path.filter(|p| !p.trim().is_empty()) {
+            self.data_path = path;
+        }
+        if let Some(volume) = config.volume {
+            self.alarm_volume = volume.clamp(0.0, 1.0);
+        }
+        if let Some(muted) = config.muted {
+            self.muted = muted;
+        }
+        if let Some(theme) = config.theme {
+            self.theme_mode = theme;
+        }
+        if let Some(presets) = config.presets {
+            self.presets = presets;
+        }
+    }
+
+    fn load_data(&mut self) {
+        if Path::new(self.data_path()).exists()
+            && let Ok(data) = fs::read_to_string(self.data_path())
+            && let Ok(persist) = serde_json::from_str::<PersistentData>(&data)
+        {
+            self.history = persist.history;
+            self.text_color = Color32::from_rgba_unmultiplied(
+                persist.text_color[0],
+                persist.text_color[1],
+                persist.text_color[2],
+                persist.text_color[3],
+            );
+            self.background_path = persist.background_path;
+            self.background_dir = persist.background_dir;
+            self.background_random = persist.background_random;
+            self.background_fit = persist.background_fit;
+            self.show_background = persist.show_background;
+            self.theme_mode = persist.theme_mode;
+            self.theme_day_start_hour = persist.theme_day_start_hour;
+            self.theme_night_start_hour = persist.theme_night_start_hour;
+            self.overlay_mode = persist.overlay_mode;
+            let [r, g, b, a] = persist.overlay_background;
+            self.overlay_background = Color32::from_rgba_unmultiplied(r, g, b, a);
+            self.status_image_path = persist.status_image_path;
+            self.status_image_interval_secs = persist.status_image_interval_secs;
+            self.status_text_enabled = persist.status_text_enabled;
+            self.status_text_path = persist.status_text_path;
+            self.keep_running_on_close = persist.keep_running_on_close;
+            self.alarm_max_secs = persist.alarm_max_secs;
+            self.alarm_pattern = persist.alarm_pattern;
+            self.extend_minutes = persist.extend_minutes;
+            self.extend_minutes_input = join_minutes(&self.extend_minutes);
+            self.auto_names = persist.auto_names;
+            self.auto_name_index = persist.auto_name_index;
+            self.auto_names_input = self.auto_names.join(",");
+            self.focus_goal_minutes = persist.focus_goal_minutes;
+            self.focus_goal_reached_on = persist.focus_goal_reached_on;
+            self.alarm_pattern_custom = !ALARM_PATTERNS.iter().any(|(_, p)| *p == self.alarm_pattern);
+            self.app_title = persist.app_title;
+            self.milestones = persist.milestones;
+            self.special_target = persist.special_target;
+            self.history_limit = persist.history_limit;
+            self.idle_pause = persist.idle_pause;
+            self.idle_threshold_secs = persist.idle_threshold_secs;
+            self.compact_time = persist.compact_time;
+            self.clock_minutes_only = persist.clock_minutes_only;
+            self.show_date = persist.show_date;
+            self.progress_color = persist.progress_color;
+            self.colorblind = persist.colorblind;
+            self.finish_hook_enabled = persist.finish_hook_enabled;
+            self.finish_hook_command = persist.finish_hook_command;
+            self.pinned_window_pos = persist.pinned_window_pos;
+            self.pinned_monitor_size = persist.pinned_monitor_size;
+            self.pinned_monitor_scale = persist.pinned_monitor_scale;
+            self.list_split = persist.list_split;
+            self.task_layout = persist.task_layout;
+            self.side_panel_width = persist.side_panel_width;
+            self.fullscreen = persist.fullscreen;
+            self.remaining_summary = persist.remaining_summary;
+            self.alarm_volume = persist.alarm_volume;
+            self.muted = persist.muted;
+            self.snooze_minutes = persist.snooze_minutes;
+            self.auto_remove_secs = persist.auto_remove_secs;
+            self.presets = persist.presets;
+            self.task_headers = persist.task_headers;
+            self.chains = persist.chains;
+            self.pomodoro = persist.pomodoro;
+            self.recurring = persist.recurring;
+            self.chess_settings = persist.chess;
+            self.realert_window_minutes = persist.realert_window_minutes;
+            self.halfway_chime = persist.halfway_chime;
+            self.hour_chime = persist.hour_chime;
+            self.add_feedback = persist.add_feedback;
+            self.scroll_on_add = persist.scroll_on_add;
+            self.scroll_on_near_finish = persist.scroll_on_near_finish;
+            self.half_hour_chime = persist.half_hour_chime;
+            self.sync_mode = persist.sync_mode;
+            self.sync_bind = persist.sync_bind;
+            self.sync_target = persist.sync_target;
+            if let Some(max_id) = self.history.iter().chain(&persist.pending_alarms).map(|t| t.id).max() {
+                self.next_task_id = max_id + 1;
+            }
+            // 关闭期间已到时的会在第一帧结束并照常提醒
+            self.tasks.extend(persist.pending_alarms);
+            self.restore_unacknowledged();
+            for timer in self.recurring.clone() {
+                self.start_recurring(&timer, Local::now());
+            }
+        }
+    }
+
+    /// 上次退出时仍未确认的近期结束任务放回任务列表，并再提醒一次；每个任务只补发一次
+    fn restore_unacknowledged(&mut self) {
+        if self.realert_window_minutes == 0 {
+            return;
+        }
+        let window = chrono::Duration::minutes(self.realert_window_minutes as i64);
+        let now = Local::now();
+        let mut pending = Vec::new();
+        for entry in &mut self.history {
+            if !entry.acknowledged && !entry.realerted && entry.finished_at.is_some_and(|at| now - at <= window) {
+                entry.realerted = true;
+                pending.push(entry.clone());
+            }
+        }
+        if pending.is_empty() {
+            return;
+        }
+        self.save_data();
+        let mut sound = false;
+        let mut important_sound = false;
+        for mut task in pending {
+            // 直接视为已到时，finished_at 已有值，tick_tasks 不会重复提醒
+            task.start = Some(Instant::now());
+            task.paused = false;
+            task.elapsed_before_pause = task.duration;
+            if task.alert_notify {
+                Self::show_notification("倒计时结束（未确认）", &task.finish_message());
+            }
+            if task.alert_sound {
+                sound = true;
+                important_sound |= task.important;
+            }
+            if !self.popup_pinned() {
+                self.show_finished_popup = Some(task.id);
+            }
+            self.tasks.push(task);
+        }
+        if important_sound {
+            self.play_important_alarm();
+        } else if sound {
+            self.play_alarm_sound();
+        }
+    }
+
+    fn save_data(&self) {
+        let persist = PersistentData {
+            history: self.history.clone(),
+            text_color: self.text_color.to_array(),
+            background_path: self.background_path.clone(),
+            background_dir: self.background_dir.clone(),
+            background_random: self.background_random,
+            background_fit: self.background_fit,
+            show_background: self.show_background,
+            theme_mode: self.theme_mode,
+            theme_day_start_hour: self.theme_day_start_hour,
+            theme_night_start_hour: self.theme_night_start_hour,
+            overlay_mode: self.overlay_mode,
+            overlay_background: self.overlay_background.to_srgba_unmultiplied(),
+            status_image_path: self.status_image_path.clone(),
+            status_image_interval_secs: self.status_image_interval_secs,
+            status_text_enabled: self.status_text_enabled,
+            status_text_path: self.status_text_path.clone(),
+            keep_running_on_close: self.keep_running_on_close,
+            alarm_max_secs: self.alarm_max_secs,
+            alarm_pattern: self.alarm_pattern,
+            extend_minutes: self.extend_minutes.clone(),
+            auto_names: self.auto_names.clone(),
+            auto_name_index: self.auto_name_index,
+            focus_goal_minutes: self.focus_goal_minutes,
+            focus_goal_reached_on: self.focus_goal_reached_on,
+            app_title: self.app_title.clone(),
+            milestones: self.milestones.clone(),
+            special_target: self.special_target.clone(),
+            history_limit: self.history_limit,
+            idle_pause: self.idle_pause,
+            idle_threshold_secs: self.idle_threshold_secs,
+            compact_time: self.compact_time,
+            clock_minutes_only: self.clock_minutes_only,
+            show_date: self.show_date,
+            progress_color: self.progress_color,
+            colorblind: self.colorblind,
+            finish_hook_enabled: self.finish_hook_enabled,
+            finish_hook_command: self.finish_hook_command.clone(),
+            pinned_window_pos: self.pinned_window_pos,
+            pinned_monitor_size: self.pinned_monitor_size,
+            pinned_monitor_scale: self.pinned_monitor_scale,
+            list_split: self.list_split,
+            task_layout: self.task_layout,
+            side_panel_width: self.side_panel_width,
+            fullscreen: self.fullscreen,
+            remaining_summary: self.remaining_summary,
+            alarm_volume: self.alarm_volume,
+            muted: self.muted,
+            snooze_minutes: self.snooze_minutes,
+            auto_remove_secs: self.auto_remove_secs,
+            presets: self.presets.clone(),
+            task_headers: Self::headers_for_saving(&self.tasks, &self.task_headers),
+            chains: self.chains.clone(),
+            pomodoro: self.pomodoro,
+            recurring: self.recurring.clone(),
+            chess: self.chess_settings,
+            pending_alarms: self.tasks.iter().filter(|t| t.is_saved_pending()).cloned().collect(),
+            realert_window_minutes: self.realert_window_minutes,
+            halfway_chime: self.halfway_chime,
+            hour_chime: self.hour_chime,
+            add_feedback: self.add_feedback,
+            scroll_on_add: self.scroll_on_add,
+            scroll_on_near_finish: self.scroll_on_near_finish,
+            half_hour_chime: self.half_hour_chime,
+            sync_mode: self.sync_mode,
+            sync_bind: self.sync_bind.clone(),
+            sync_target: self.sync_target.clone(),
+        };
+        if let Ok(json) = serde_json::to_string_pretty(&persist) {
+            let _ = fs::write(self.data_path(), json);
+        }
+    }
+
+    /// 解析 `秒`、`MM:SS`、`HH:MM:SS` 或 `1h30m` 这样带单位（h/m/s）的时长，
+    /// 各项之间可以用 `+`、`-` 从左到右加减（`5m+30s`、`1h-10m`）。分、秒字段超过 59
+    /// 时按进位换算（`1:70` 即 130 秒），不会被拒绝；无法解析、有空项、结果为负或超过 `MAX_DURATION` 时返回 `None`。
+    fn parse_duration(input: &str) -> Option<Duration> {
+        let mut total: i128 = 0;
+        let mut negative = false;
+        let mut rest = input.trim();
+        loop {
+            let (term, next) = match rest.find(['+', '-']) {
+                Some(pos) => (&rest[..pos], Some((&rest[pos..pos + 1], &rest[pos + 1..]))),
+                None => (rest, None),
+            };
+            let secs = Self::parse_duration_term(term.trim())? as i128;
+            total = if negative { total - secs } else { total + secs };
+            let Some((op, after)) = next else {
+                break;
+            };
+            negative = op == "-";
+            rest = after;
+        }
+        u64::try_from(total)
+            .ok()
+            .map(Duration::from_secs)
+            .filter(|d| *d <= MAX_DURATION)
+    }
+
+    /// 解析用逗号分隔的多个提前提醒时长（`5:00, 1:00`），返回从大到小去重后的秒数；
+    /// 每一项都必须短于倒计时时长 `dur`
+    fn parse_lead_reminders(input: &str, dur: Duration) -> Result<Vec<u64>, String> {
+        let mut leads = Vec::new();
+        for item in input.split([',', '，']).map(str::trim).filter(|item| !item.is_empty()) {
+            match Self::parse_duration(item) {
+                Some(lead) if lead.is_zero() => return Err("提前提醒的时间不能为 0".to_string()),
+                Some(lead) if lead < dur => leads.push(lead.as_secs()),
+                Some(_) => return Err(format!("提前提醒的时间 {} 必须短于倒计时时长", item)),
+                None => return Err(format!("提前提醒的时间 {} 格式不正确", item)),
+            }
+        }
+        leads.sort_unstable_by(|a, b| b.cmp(a));
+        leads.dedup();
+        Ok(leads)
+    }
+
+    /// 表达式中的一项，返回秒数
+    fn parse_duration_term(term: &str) -> Option<u64> {
+        if term.ends_with(|c: char| c.is_ascii_alphabetic()) {
+            return Self::parse_unit_term(term);
+        }
+        let parts: Vec<&str> = term.split(':').collect();
+        match parts.len() {
+            1 => parts[0].parse::<u64>().ok(),
+            2 => {
+                let mins = parts[0].parse::<u64>().ok()?;
+                let secs = parts[1].parse::<u64>().ok()?;
+                mins.checked_mul(60)?.checked_add(secs)
+            }
+            3 => {
+                let hours = parts[0].parse::<u64>().ok()?;
+                let mins = parts[1].parse::<u64>().ok()?;
+                let secs = parts[2].parse::<u64>().ok()?;
+                hours.checked_mul(3600)?.checked_add(mins.checked_mul(60)?)?.checked_add(secs)
+            }
+            _ => None,
+        }
+    }
+
+    /// 由“数字+单位”组成的一项，如 `5m`、`1h30m`，单位不区分大小写
+    fn parse_unit_term(term: &str) -> Option<u64> {
+        let mut total: u64 = 0;
+        let mut rest = term;
+        while !rest.is_empty() {
+            let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+            let value = rest[..digits].parse::<u64>().ok()?;
+            let unit = match rest[digits..].chars().next()?.to_ascii_lowercase() {
+                'h' => 3600,
+                'm' => 60,
+                's' => 1,
+                _ => return None,
+            };
+            total = total.checked_add(value.checked_mul(unit)?)?;
+            rest = &rest[digits + 1..];
+        }
+        Some(total)
+    }
+
+    /// 当前应显示的窗口标题，其他需要改写标题的功能也应在这里组合
+    fn window_title(&self) -> String {
+        let title = self.app_title.trim();
+        if title.is_empty() {
+            DEFAULT_APP_TITLE.to_string()
+        } else {
+            title.to_string()
+        }
+    }
+
+    /// 解析特别倒计时的目标，返回目标时刻和任务名
+    fn special_target_time(&self) -> Result<(DateTime<Local>, String), String> {
+        let input = self.special_target.trim();
+        if input.is_empty() {
+            let midnight = (Local::now().date_naive() + chrono::Duration::days(1))
+                .and_hms_opt(0, 0, 0)
+                .expect("midnight is a valid time");
+            let target = Local
+                .from_local_datetime(&midnight)
+                .earliest()
+                .ok_or("无法确定午夜时间")?;
+            return Ok((target, "午夜".to_string()));
+        }
+        let naive = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M")
+            .or_else(|_| {
+                NaiveDate::parse_from_str(input, "%Y-%m-%d")
+                    .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight is a valid time"))
+            })
+            .map_err(|_| "目标时间格式应为 YYYY-MM-DD 或 YYYY-MM-DD HH:MM".to_string())?;
+        let target = Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .ok_or("该时间在本地时区不存在")?;
+        if target <= Local::now() {
+            return Err("目标时间已经过去".to_string());
+        }
+        Ok((target, input.to_string()))
+    }
+
+    fn start_special_countdown(&mut self) {
+        let (target, name) = match self.special_target_time() {
+            Ok(target) => target,
+            Err(msg) => {
+                self.special_status = Some(msg);
+                return;
+            }
+        };
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        // 按墙钟计算并随数据保存，几天甚至几个月后的目标在重启后也能接着倒数
+        let mut task = CountdownTask::alarm(id, name, target);
+        task.special = true;
+        task.source = TaskSource::Special;
+        self.tasks.push(task);
+        self.special_status = None;
+        self.save_data();
+    }
+
+    /// 历史记录变化后重新统计常用任务：按任务名和时长分组计数，次数多的在前，次数相同时最近完成的在前
+    fn refresh_frequent(&mut self) {
+        let key = (self.history.len(), self.history.last().map(|t| t.id));
+        if self.frequent_key == Some(key) {
+            return;
+        }
+        self.frequent_key = Some(key);
+
+        let mut groups: Vec<(FrequentTask, usize)> = Vec::new();
+        for (order, task) in self
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| !t.special && t.kind == TaskKind::Countdown)
+        {
+            match groups
+                .iter_mut()
+                .find(|(g, _)| g.name == task.name && g.duration == task.duration)
+            {
+                Some((group, latest)) => {
+                    group.count += 1;
+                    *latest = order;
+                }
+                None => groups.push((
+                    FrequentTask {
+                        name: task.name.clone(),
+                        input: task.input.clone(),
+                        duration: task.duration,
+                        count: 1,
+                    },
+                    order,
+                )),
+            }
+        }
+        groups.retain(|(g, _)| g.count >= FREQUENT_MIN_COUNT);
+        groups.sort_by(|(a, a_latest), (b, b_latest)| b.count.cmp(&a.count).then(b_latest.cmp(a_latest)));
+        self.frequent = groups.into_iter().take(FREQUENT_LIMIT).map(|(g, _)| g).collect();
+    }
+
+    fn start_frequent(&mut self, index: usize) {
+        let Some(frequent) = self.frequent.get(index) else {
+            return;
+        };
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        let mut task = CountdownTask::new(id, frequent.name.clone(), frequent.input.clone(), frequent.duration);
+        task.source = TaskSource::Frequent;
+        self.tasks.push(task);
+        self.confirm_added(id);
+    }
+
+    fn start_alarm(&mut self) {
+        let at = match parse_alarm_time(&self.alarm_input, Local::now()) {
+            Ok(at) => at,
+            Err(msg) => {
+                self.alarm_status = Some(msg);
+                return;
+            }
+        };
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        let name = if self.alarm_name.trim().is_empty() {
+            format!("闹钟 {}", at.format("%H:%M"))
+        } else {
+            self.alarm_name.trim().to_string()
+        };
+        self.tasks.push(CountdownTask::alarm(id, name, at));
+        self.confirm_added(id);
+        self.save_data();
+        self.alarm_name.clear();
+        self.alarm_input.clear();
+        self.alarm_status = None;
+    }
+
+    /// 以相同的任务名和时长重新开始最近结束的任务
+    fn repeat_last_finished(&mut self) {
+        if let Some(last) = self.last_finished.clone() {
+            self.restart_task(&last, TaskSource::Repeat);
+        }
+    }
+
+    /// 特别倒计时和闹钟的目标时间已过，不能重新开始
+    fn can_restart(task: &CountdownTask) -> bool {
+        !task.special && !matches!(task.kind, TaskKind::Alarm { .. })
+    }
+
+    /// 以相同的任务名、时长和个性化设置新建任务
+    fn restart_task(&mut self, template: &CountdownTask, source: TaskSource) {
+        if !Self::can_restart(template) {
+            return;
+        }
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        let mut task = match template.kind {
+            TaskKind::Stopwatch => CountdownTask::stopwatch(id, template.name.clone()),
+            _ => CountdownTask::new(id, template.name.clone(), template.input.clone(), template.duration),
+        };
+        task.source = source;
+        task.inherit_options(template);
+        self.tasks.push(task);
+    }
+
+    /// 复制进行中的任务：相同的任务名和设定时长，从现在开始计时
+    fn duplicate_task(&mut self, template: &CountdownTask) {
+        let id = self.next_task_id;
+        self.restart_task(template, TaskSource::Duplicate);
+        self.confirm_added(id);
+    }
+
+    /// 按添加表单创建任务；时长为 0 时提示，过长时需 `confirmed` 才创建
+    fn add_task_from_form(&mut self, confirmed: bool) {
+        let Some(dur) = Self::parse_duration(&self.new_task_input) else {
+            self.add_error = Some("时长格式不正确或超过一年，点“?”查看支持的写法".to_string());
+            return;
+        };
+        if dur.is_zero() {
+            self.add_error = Some("时长不能为 0".to_string());
+            return;
+        }
+        let leads = match Self::parse_lead_reminders(&self.new_task_lead_input, dur) {
+            Ok(leads) => leads,
+            Err(msg) => {
+                self.add_error = Some(msg);
+                return;
+            }
+        };
+        let start_at = match self.new_task_start_input.trim() {
+            "" => None,
+            input => match parse_alarm_time(input, Local::now()) {
+                Ok(at) => Some(at),
+                Err(msg) => {
+                    self.add_error = Some(format!("开始时间：{}", msg));
+                    return;
+                }
+            },
+        };
+        if dur > LONG_DURATION_WARN && !confirmed {
+            self.add_error = Some(format!("时长为 {}，超过 24 小时，确定要添加吗？", format_duration_words(dur)));
+            self.confirm_long_add = true;
+            return;
+        }
+
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        let name = if self.new_task_name.trim().is_empty() {
+            self.next_auto_name(id)
+        } else {
+            self.new_task_name.trim().to_string()
+        };
+        let mut task = CountdownTask::new(id, name, self.new_task_input.clone(), dur);
+        task.finish_text = self.new_task_finish_text.trim().to_string();
+        task.note = self.new_task_note.trim().to_string();
+        task.alert_sound = self.new_task_sound;
+        task.alert_notify = self.new_task_notify;
+        task.count_overtime = self.new_task_overtime;
+        task.auto_remove = self.new_task_auto_remove;
+        task.lead_reminders = leads;
+        task.tags = parse_tags(&self.new_task_tags);
+        task.ambient = self.new_task_ambient.clone();
+        if let Some(at) = start_at {
+            task.schedule_start(at);
+        }
+        self.ambient_status = None;
+        self.tasks.push(task);
+        self.confirm_added(id);
+        self.new_task_input.clear();
+        self.new_task_name.clear();
+        self.new_task_finish_text.clear();
+        self.new_task_note.clear();
+        self.new_task_lead_input.clear();
+        self.new_task_start_input.clear();
+        self.add_error = None;
+        self.confirm_long_add = false;
+    }
+
+    /// 用表单中的任务名开始一个秒表，时长等选项不适用
+    fn start_stopwatch(&mut self) {
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        let name = if self.new_task_name.trim().is_empty() {
+            self.next_auto_name(id)
+        } else {
+            self.new_task_name.trim().to_string()
+        };
+        let mut task = CountdownTask::stopwatch(id, name);
+        task.tags = parse_tags(&self.new_task_tags);
+        task.ambient = self.new_task_ambient.clone();
+        self.ambient_status = None;
+        self.tasks.push(task);
+        self.confirm_added(id);
+        self.new_task_name.clear();
+    }
+
+    /// 停止秒表：以实际用时作为设定时长记入历史记录，并从任务列表移除
+    fn stop_stopwatch(&mut self, id: usize) {
+        let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) else {
+            return;
+        };
+        task.pause();
+        task.duration = task.elapsed();
+        task.input = format_hms(task.duration, true);
+        task.finished_at = Some(Local::now());
+        task.acknowledged = true;
+        self.history.push(task.clone());
+        self.trim_history();
+        self.save_data();
+        self.remove_tasks(&[id]);
+    }
+
+    /// 任务名留空时的默认名：按顺序轮流使用设置中的名字列表，列表为空时为“任务#编号”
+    fn next_auto_name(&mut self, id: usize) -> String {
+        if self.auto_names.is_empty() {
+            return format!("任务#{}", id);
+        }
+        let name = self.auto_names[self.auto_name_index % self.auto_names.len()].clone();
+        self.auto_name_index = (self.auto_name_index + 1) % self.auto_names.len();
+        self.save_data();
+        name
+    }
+
+    fn start_preset(&mut self, index: usize) {
+        let Some(preset) = self.presets.get(index) else {
+            return;
+        };
+        let Some(duration) = Self::parse_duration(&preset.input).filter(|d| !d.is_zero()) else {
+            return;
+        };
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        let mut task = CountdownTask::new(id, preset.name.clone(), preset.input.clone(), duration);
+        task.source = TaskSource::Preset;
+        task.alert_sound = preset.alert_sound;
+        task.color = preset.color;
+        task.tags = preset.tags.clone();
+        task.alarm_pattern = preset.alarm_pattern;
+        task.ambient = preset.ambient.clone();
+        let start_sound = preset.start_sound;
+        self.tasks.push(task);
+        self.confirm_added(id);
+        if start_sound {
+            self.play_start_sound();
+        }
+    }
+
+    /// 手动添加任务后的确认反馈：一声短促的轻响，并让新任务的卡片高亮片刻
+    fn confirm_added(&mut self, id: usize) {
+        if self.scroll_on_add {
+            self.scroll_to_task = Some(id);
+        }
+        if !self.add_feedback {
+            return;
+        }
+        self.added_highlight = Some((id, Instant::now()));
+        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
+            sink.set_volume(self.effective_volume());
+            sink.append(
+                SineWave::new(1200.0)
+                    .take_duration(Duration::from_millis(40))
+                    .amplify(0.12),
+            );
+            self.track_sink(sink);
+        }
+    }
+
+    /// 把添加表单中的任务名、时长和响铃设置存为预设，已有同名预设时先请求确认覆盖
+    fn save_form_as_preset(&mut self) {
+        let name = self.new_task_name.trim();
+        if name.is_empty() {
+            self.preset_status = Some("请先填写任务名".to_string());
+            return;
+        }
+        if Self::parse_duration(&self.new_task_input).is_none_or(|d| d.is_zero()) {
+            self.preset_status = Some("时长格式不正确".to_string());
+            return;
+        }
+        let preset = QuickPreset {
+            name: name.to_string(),
+            input: self.new_task_input.trim().to_string(),
+            alert_sound: self.new_task_sound,
+            color: None,
+            tags: parse_tags(&self.new_task_tags),
+            start_sound: false,
+            alarm_pattern: None,
+            ambient: self.new_task_ambient.clone(),
+        };
+        if self.presets.iter().any(|p| p.name == preset.name) {
+            self.preset_overwrite = Some(preset);
+        } else {
+            self.presets.push(preset);
+            self.preset_status = None;
+            self.save_data();
+        }
+    }
+
+    /// 用新的预设替换同名预设（只保留第一个位置）
+    fn overwrite_preset(&mut self, preset: QuickPreset) {
+        match self.presets.iter().position(|p| p.name == preset.name) {
+            Some(index) => {
+                self.presets.retain(|p| p.name != preset.name);
+                self.presets.insert(index, preset);
+            }
+            None => self.presets.push(preset),
+        }
+        self.preset_status = None;
+        self.save_data();
+    }
+
+    /// 开始连续倒计时中 `link` 指向的一步，超出范围或时长无效时什么也不做
+    fn start_chain_step(&mut self, link: ChainLink) {
+        let (chain, index, round) = (&link.chain, link.index, link.round);
+        let Some(step) = chain.steps.get(index) else {
+            return;
+        };
+        let Some(duration) = Self::parse_duration(&step.input).filter(|d| !d.is_zero()) else {
+            return;
+        };
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        let mut name = format!("{} · {}（{}/{}）", chain.name, step.name, index + 1, chain.steps.len());
+        if chain.rounds > 1 {
+            name += &format!(" 第 {}/{} 轮", round + 1, chain.rounds);
+        }
+        let mut task = CountdownTask::new(id, name, step.input.clone(), duration);
+        task.source = TaskSource::Chain;
+        task.chain = Some(link);
+        self.tasks.push(task);
+    }
+
+    fn start_pomodoro_phase(&mut self, link: PomodoroLink) {
+        let duration = Duration::from_secs(link.minutes() * 60);
+        if duration.is_zero() {
+            return;
+        }
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        let phase = match (link.on_break, link.cycle >= link.settings.cycles) {
+            (false, _) => "专注",
+            (true, false) => "休息",
+            (true, true) => "长休息",
+        };
+        let name = format!("番茄钟 · {}（{}/{}）", phase, link.cycle, link.settings.cycles);
+        let mut task = CountdownTask::new(id, name, format_hms(duration, true), duration);
+        task.pomodoro = Some(link);
+        self.tasks.push(task);
+    }
+
+    /// 按规则安排重复计时在 `after` 之后的下一次：按间隔的为倒计时，按时刻的为闹钟
+    fn start_recurring(&mut self, timer: &RecurringTimer, after: DateTime<Local>) {
+        let id = self.next_task_id;
+        let mut task = match timer.rule {
+            RepeatRule::Every { minutes } => {
+                let duration = Duration::from_secs(minutes * 60);
+                CountdownTask::new(id, timer.name.clone(), format_hms(duration, true), duration)
+            }
+            RepeatRule::Daily { .. } | RepeatRule::Weekdays { .. } => {
+                let Some(at) = timer.rule.next_after(after) else {
+                    return;
+                };
+                CountdownTask::alarm(id, timer.name.clone(), at)
+            }
+        };
+        self.next_task_id += 1;
+        task.source = TaskSource::Recurring;
+        task.recurring = Some(timer.id);
+        self.tasks.push(task);
+    }
+
+    fn add_recurring(&mut self) {
+        let rule = match self.recurring_rule {
+            RepeatRule::Every { minutes: 0 } => {
+                self.recurring_status = Some("间隔不能为 0".to_string());
+                return;
+            }
+            RepeatRule::Every { minutes } => RepeatRule::Every { minutes },
+            RepeatRule::Daily { .. } | RepeatRule::Weekdays { .. } => {
+                let Ok(time) = NaiveTime::parse_from_str(self.recurring_time.trim(), "%H:%M") else {
+                    self.recurring_status = Some("时刻格式应为 HH:MM".to_string());
+                    return;
+                };
+                match self.recurring_rule {
+                    RepeatRule::Daily { .. } => RepeatRule::Daily { time },
+                    _ => RepeatRule::Weekdays { time },
+                }
+            }
+        };
+        let name = match self.recurring_name.trim() {
+            "" => rule.describe(),
+            name => name.to_string(),
+        };
+        let timer = RecurringTimer {
+            id: self.recurring.iter().map(|r| r.id + 1).max().unwrap_or(0),
+            name,
+            rule,
+        };
+        self.start_recurring(&timer, Local::now());
+        self.recurring.push(timer);
+        self.recurring_name.clear();
+        self.recurring_status = None;
+        self.save_data();
+    }
+
+    /// 删除重复计时，连同尚未结束的那一次
+    fn remove_recurring(&mut self, id: usize) {
+        self.recurring.retain(|r| r.id != id);
+        let pending: Vec<usize> = self
+            .tasks
+            .iter()
+            .filter(|t| t.recurring == Some(id) && !t.is_finished())
+            .map(|t| t.id)
+            .collect();
+        self.remove_tasks(&pending);
+        self.save_data();
+    }
+
+    fn show_recurring(&mut self, ui: &mut egui::Ui) {
+        use egui::{ComboBox, DragValue, TextEdit};
+
+        let mut remove = None;
+        for timer in &self.recurring {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} · {}", timer.name, timer.rule.describe()));
+                if ui.small_button("删除").clicked() {
+                    remove = Some(timer.id);
+                }
+            });
+        }
+        if let Some(id) = remove {
+            self.remove_recurring(id);
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.recurring_name).hint_text("名称（可选）").desired_width(100.0));
+            let midnight = NaiveTime::MIN;
+            let choices = [
+                ("每隔", RepeatRule::Every { minutes: 30 }),
+                ("每天", RepeatRule::Daily { time: midnight }),
+                ("工作日", RepeatRule::Weekdays { time: midnight }),
+            ];
+            let current = choices
+                .iter()
+                .find(|(_, c)| std::mem::discriminant(c) == std::mem::discriminant(&self.recurring_rule))
+                .map_or("", |(label, _)| label);
+            ComboBox::from_id_source("recurring_rule")
+                .selected_text(current)
+                .show_ui(ui, |ui| {
+                    for (label, choice) in choices {
+                        let selected = std::mem::discriminant(&choice) == std::mem::discriminant(&self.recurring_rule);
+                        if ui.selectable_label(selected, label).clicked() && !selected {
+                            self.recurring_rule = choice;
+                        }
+                    }
+                });
+            match &mut self.recurring_rule {
+                RepeatRule::Every { minutes } => {
+                    ui.add(DragValue::new(minutes).clamp_range(1..=24 * 60).suffix(" 分钟"));
+                }
+                RepeatRule::Daily { .. } | RepeatRule::Weekdays { .. } => {
+                    ui.add(TextEdit::singleline(&mut self.recurring_time).hint_text("08:00").desired_width(60.0));
+                }
+            }
+            if ui.button("添加").clicked() {
+                self.add_recurring();
+            }
+        });
+        if let Some(status) = &self.recurring_status {
+            error_label(ui, self.palette(), status);
+        }
+    }
+
+    /// 到了定时开始时间的任务开始计时
+    fn tick_scheduled_starts(&mut self) {
+        let now = Local::now();
+        for task in &mut self.tasks {
+            if task.scheduled_start.is_some_and(|at| at <= now) {
+                task.resume();
+            }
+        }
+    }
+
+    /// 设为自动移除的任务结束满保留时间后确认并移出列表；重要任务仍需在弹窗中手动确认
+    fn tick_auto_remove(&mut self) {
+        let grace = chrono::Duration::seconds(self.auto_remove_secs as i64);
+        let now = Local::now();
+        let due: Vec<usize> = self
+            .tasks
+            .iter()
+            .filter(|t| t.auto_remove && !t.important)
+            .filter(|t| t.finished_at.is_some_and(|at| now - at >= grace))
+            .map(|t| t.id)
+            .collect();
+        if due.is_empty() {
+            return;
+        }
+        for &id in &due {
+            self.acknowledge_task(id);
+            if self.show_finished_popup == Some(id) {
+                self.show_finished_popup = None;
+            }
+        }
+        self.remove_tasks(&due);
+    }
+
+    /// 已结束但未确认的高优先级任务每隔 `HIGH_PRIORITY_REPEAT` 再响一次，直到确认
+    fn tick_high_priority_alarms(&mut self) {
+        // 有重要任务时响重要任务的提醒音
+        let mut ring = None;
+        for task in &mut self.tasks {
+            if task.priority != Priority::High || !task.alert_sound || task.acknowledged || task.finished_at.is_none() {
+                continue;
+            }
+            match task.rung_at {
+                // 第一次提醒已在结束时响过
+                None => task.rung_at = Some(Instant::now()),
+                Some(at) if at.elapsed() >= HIGH_PRIORITY_REPEAT => {
+                    task.rung_at = Some(Instant::now());
+                    ring = Some(ring.unwrap_or(false) || task.important);
+                }
+                Some(_) => {}
+            }
+        }
+        match ring {
+            Some(true) => self.play_important_alarm(),
+            Some(false) => self.play_alarm_sound(),
+            None => {}
+        }
+    }
+
+    /// 棋钟一方超时时和倒计时结束一样响铃并发送通知
+    fn tick_chess(&mut self) {
+        let Some(side) = self.chess.as_mut().and_then(|c| c.tick(Instant::now())) else {
+            return;
+        };
+        self.play_alarm_sound();
+        Self::show_notification("棋钟", &format!("{}超时", CHESS_SIDES[side]));
+    }
+
+    fn show_chess_clock(&mut self, ui: &mut egui::Ui) {
+        use chess_clock::{ChessClock, Increment};
+        use egui::{vec2, Button, ComboBox, DragValue, RichText};
+
+        let palette = self.palette();
+        let Some(clock) = &mut self.chess else {
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.label("每方");
+                changed |= ui
+                    .add(DragValue::new(&mut self.chess_settings.base_minutes).clamp_range(1..=180).suffix(" 分钟"))
+                    .changed();
+                ComboBox::from_id_source("chess_increment")
+                    .selected_text(self.chess_settings.increment.label())
+                    .show_ui(ui, |ui| {
+                        for mode in Increment::ALL {
+                            changed |= ui
+                                .selectable_value(&mut self.chess_settings.increment, mode, mode.label())
+                                .changed();
+                        }
+                    })
+                    .response
+                    .on_hover_text("费舍尔：每步固定加时；布朗斯坦：返还本步用时，最多为加时");
+                if self.chess_settings.increment != Increment::None {
+                    changed |= ui
+                        .add(DragValue::new(&mut self.chess_settings.increment_secs).clamp_range(0..=60).suffix(" 秒"))
+                        .changed();
+                }
+            });
+            if changed {
+                self.save_data();
+            }
+            if ui.button("摆好棋钟").on_hover_text("按下一方的钟后对方开始走时").clicked() {
+                self.chess = Some(ChessClock::new(self.chess_settings));
+            }
+            return;
+        };
+
+        let now = Instant::now();
+        ui.columns(2, |columns| {
+            for (side, ui) in columns.iter_mut().enumerate() {
+                let remaining = clock.remaining(side, now);
+                let mut text = RichText::new(format_hms(remaining, true)).size(40.0);
+                if clock.flagged == Some(side) {
+                    text = text.color(palette.error);
+                }
+                let mut button = Button::new(text).min_size(vec2(ui.available_width(), 80.0));
+                if clock.active() == Some(side) {
+                    button = button.fill(ui.visuals().selection.bg_fill);
+                }
+                ui.label(CHESS_SIDES[side]);
+                if ui.add(button).on_hover_text("走完一步后按自己的钟").clicked() {
+                    clock.press(side, now);
+                }
+            }
+        });
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if clock.is_paused() {
+                if ui.button("继续").clicked() {
+                    clock.resume(now);
+                }
+            } else if clock.active().is_some() && ui.button("暂停").clicked() {
+                clock.pause(now);
+            }
+            close = ui.button("收起棋钟").clicked();
+        });
+        if close {
+            self.chess = None;
+        }
+    }
+
+    /// 今天完成的番茄（专注阶段）个数
+    fn pomodoros_today(&self) -> usize {
+        let today = Local::now().date_naive();
+        self.history
+            .iter()
+            .filter(|t| t.pomodoro.is_some_and(|p| !p.on_break))
+            .filter(|t| t.finished_at.is_some_and(|at| at.date_naive() == today))
+            .count()
+    }
+
+    fn show_pomodoro_settings(&mut self, ui: &mut egui::Ui) {
+        use egui::{DragValue, Grid};
+
+        let mut changed = false;
+        Grid::new("pomodoro_settings").show(ui, |ui| {
+            for (label, value) in [
+                ("专注", &mut self.pomodoro.work_minutes),
+                ("休息", &mut self.pomodoro.break_minutes),
+                ("长休息", &mut self.pomodoro.long_break_minutes),
+            ] {
+                ui.label(label);
+                changed |= ui
+                    .add(DragValue::new(value).clamp_range(0..=180).suffix(" 分钟"))
+                    .changed();
+                ui.end_row();
+            }
+            ui.label("每轮专注次数");
+            changed |= ui
+                .add(DragValue::new(&mut self.pomodoro.cycles).clamp_range(1..=12))
+                .changed();
+            ui.end_row();
+        });
+        if changed {
+            self.save_data();
+        }
+        ui.horizontal(|ui| {
+            let can_start = self.pomodoro.work_minutes > 0;
+            if ui.add_enabled(can_start, egui::Button::new("开始一轮")).clicked() {
+                self.start_pomodoro_phase(PomodoroLink {
+                    settings: self.pomodoro,
+                    cycle: 1,
+                    on_break: false,
+                });
+            }
+            ui.label(format!("今天已完成 {} 个番茄", self.pomodoros_today()));
+        });
+    }
+
+    /// 所有尚未开始的计时，按预计开始时间排序；每帧重新计算，触发后自然消失
+    fn planned_items(&self) -> Vec<PlannedItem> {
+        let now = Local::now();
+        let mut items = Vec::new();
+        for task in &self.tasks {
+            if let Some(at) = task.scheduled_start {
+                items.push(PlannedItem {
+                    name: task.name.clone(),
+                    starts_at: Some(at),
+                    cancel: PlannedCancel::ScheduledTask { task_id: task.id },
+                });
+            }
+            if let Some(timer) = task.recurring.and_then(|id| self.recurring.iter().find(|r| r.id == id))
+                && !task.is_finished()
+            {
+                let starts_at = match task.kind {
+                    TaskKind::Alarm { at } => Some(at),
+                    _ => (!task.paused)
+                        .then(|| chrono::Duration::from_std(task.remaining()).ok())
+                        .flatten()
+                        .map(|d| now + d),
+                };
+                items.push(PlannedItem {
+                    name: format!("{}（{}）", task.name, timer.rule.describe()),
+                    starts_at,
+                    cancel: PlannedCancel::RecurringNext { task_id: task.id },
+                });
+            } else if let TaskKind::Alarm { at } = task.kind
+                && task.recurring.is_none()
+                && !task.is_finished()
+            {
+                items.push(PlannedItem {
+                    name: format!("⏰ {}", task.name),
+                    starts_at: Some(at),
+                    cancel: PlannedCancel::PendingAlarm { task_id: task.id },
+                });
+            }
+            let Some(link) = &task.chain else {
+                continue;
+            };
+            // 后续每一步在前面所有步骤结束后开始
+            let mut offset = (!task.paused && !task.is_finished()).then(|| task.remaining());
+            for (round, step_index) in link.upcoming() {
+                let step = &link.chain.steps[step_index];
+                let mut name = format!("{} · {}", link.chain.name, step.name);
+                if link.chain.rounds > 1 {
+                    name += &format!("（第 {}/{} 轮）", round + 1, link.chain.rounds);
+                }
+                items.push(PlannedItem {
+                    name,
+                    starts_at: offset
+                        .and_then(|d| chrono::Duration::from_std(d).ok())
+                        .map(|d| now + d),
+                    cancel: PlannedCancel::ChainStep {
+                        task_id: task.id,
+                        round,
+                        step: step_index,
+                    },
+                });
+                offset = offset.zip(Self::parse_duration(&step.input)).map(|(a, b)| a + b);
+            }
+        }
+        items.sort_by_key(|item| (item.starts_at.is_none(), item.starts_at));
+        items
+    }
+
+    fn cancel_planned(&mut self, cancel: PlannedCancel) {
+        match cancel {
+            PlannedCancel::ChainStep { task_id, round, step } => {
+                // 只取消这一轮的这一步，其他轮次照常进行
+                if let Some(link) = self
+                    .tasks
+                    .iter_mut()
+                    .find(|t| t.id == task_id)
+                    .and_then(|t| t.chain.as_mut())
+                    && link.upcoming().any(|pos| pos == (round, step))
+                {
+                    link.skipped.push((round, step));
+                }
+            }
+            PlannedCancel::ScheduledTask { task_id } | PlannedCancel::PendingAlarm { task_id } => {
+                self.delete_tasks(&[task_id])
+            }
+            PlannedCancel::RecurringNext { task_id } => {
+                let Some(task) = self.tasks.iter().find(|t| t.id == task_id) else {
+                    return;
+                };
+                let after = match task.kind {
+                    TaskKind::Alarm { at } => at,
+                    _ => Local::now(),
+                };
+                let timer = task.recurring.and_then(|id| self.recurring.iter().find(|r| r.id == id)).cloned();
+                self.delete_tasks(&[task_id]);
+                if let Some(timer) = timer {
+                    self.start_recurring(&timer, after);
+                }
+            }
+        }
+    }
+
+    fn show_planned(&mut self, ui: &mut egui::Ui) {
+        let items = self.planned_items();
+        if items.is_empty() {
+            ui.label("没有待开始的计时");
+            return;
+        }
+        let mut cancel = None;
+        for item in &items {
+            ui.horizontal(|ui| {
+                let when = match item.starts_at {
+                    Some(at) => at.format("%H:%M:%S").to_string(),
+                    None => "暂停中".to_string(),
+                };
+                ui.label(format!("{}  {}", when, item.name));
+                if ui.small_button("取消").clicked() {
+                    cancel = Some(item.cancel);
+                }
+            });
+        }
+        if let Some(c) = cancel {
+            self.cancel_planned(c);
+        }
+    }
+
+    /// 检查连续倒计时的名称和每一步的时长，返回错误提示
+    fn validate_chain(chain: &CountdownChain) -> Result<(), String> {
+        if chain.name.trim().is_empty() {
+            return Err("请填写连续倒计时的名称".to_string());
+        }
+        if chain.steps.is_empty() {
+            return Err("至少需要一步".to_string());
+        }
+        for (i, step) in chain.steps.iter().enumerate() {
+            if Self::parse_duration(&step.input).is_none_or(|d| d.is_zero()) {
+                return Err(format!("第 {} 步的时长格式不正确", i + 1));
+            }
+        }
+        Ok(())
+    }
+
+    /// 保存编辑中的连续倒计时，已有同名时先请求确认覆盖
+    fn save_chain_draft(&mut self) {
+        let mut chain = self.chain_draft.clone();
+        chain.name = chain.name.trim().to_string();
+        for step in &mut chain.steps {
+            step.name = step.name.trim().to_string();
+            step.input = step.input.trim().to_string();
+        }
+        if let Err(msg) = Self::validate_chain(&chain) {
+            self.chain_status = Some(msg);
+            return;
+        }
+        if self.chains.iter().any(|c| c.name == chain.name) {
+            self.chain_overwrite = Some(chain);
+        } else {
+            self.chains.push(chain);
+            self.chain_draft = CountdownChain::default();
+            self.chain_status = None;
+            self.save_data();
+        }
+    }
+
+    fn overwrite_chain(&mut self, chain: CountdownChain) {
+        match self.chains.iter().position(|c| c.name == chain.name) {
+            Some(index) => self.chains[index] = chain,
+            None => self.chains.push(chain),
+        }
+        self.chain_draft = CountdownChain::default();
+        self.chain_status = None;
+        self.save_data();
+    }
+
+    /// 已保存的连续倒计时列表和编辑表单
+    fn show_chain_builder(&mut self, ui: &mut egui::Ui) {
+        use egui::{Button, DragValue, RichText, TextEdit};
+
+        let mut start = None;
+        let mut edit = None;
+        let mut remove = None;
+        for (index, chain) in self.chains.iter().enumerate() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new(&chain.name).strong());
+                let steps: Vec<String> = chain.steps.iter().map(|s| format!("{} {}", s.name, s.input)).collect();
+                ui.label(steps.join(" → "));
+                if chain.rounds > 1 {
+                    ui.label(format!("× {} 轮", chain.rounds));
+                }
+                if ui.small_button("开始").clicked() {
+                    start = Some(index);
+                }
+                if ui.small_button("编辑").clicked() {
+                    edit = Some(index);
+                }
+                if ui.small_button("删除").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = start {
+            self.start_chain_step(ChainLink::new(self.chains[index].clone()));
+        }
+        if let Some(index) = edit {
+            self.chain_draft = self.chains[index].clone();
+            self.chain_status = None;
+        }
+        if let Some(index) = remove {
+            self.chains.remove(index);
+            self.save_data();
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("名称:");
+            ui.add(TextEdit::singleline(&mut self.chain_draft.name).hint_text("例如：煮饭"));
+        });
+        let mut remove_step = None;
+        for (i, step) in self.chain_draft.steps.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}.", i + 1));
+                ui.add(TextEdit::singleline(&mut step.name).hint_text("步骤名").desired_width(100.0));
+                ui.add(TextEdit::singleline(&mut step.input).hint_text("时长").desired_width(70.0));
+                optional_color_edit(ui, &mut step.color, "进行到这一步时任务卡片使用的颜色");
+                if ui.small_button("✖").clicked() {
+                    remove_step = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove_step {
+            self.chain_draft.steps.remove(i);
+        }
+        ui.horizontal(|ui| {
+            ui.label("重复");
+            ui.add(DragValue::new(&mut self.chain_draft.rounds).clamp_range(1..=99).suffix(" 轮"));
+            ui.checkbox(&mut self.chain_draft.quiet_transitions, "步骤间只响提示音")
+                .on_hover_text("中间步骤结束时不弹窗、不发通知，适合间歇训练");
+        });
+        ui.horizontal(|ui| {
+            if ui.button("＋ 添加一步").clicked() {
+                self.chain_draft.steps.push(ChainStep::default());
+            }
+            let has_content = !self.chain_draft.name.is_empty() || !self.chain_draft.steps.is_empty();
+            if ui.add_enabled(has_content, Button::new("保存")).clicked() {
+                self.save_chain_draft();
+            }
+            if has_content && ui.button("清空").clicked() {
+                self.chain_draft = CountdownChain::default();
+                self.chain_status = None;
+            }
+        });
+        if let Some(status) = &self.chain_status {
+            error_label(ui, self.palette(), status);
+        }
+    }
+
+    /// 预设编辑器：拖动按钮调整顺序，双击按钮修改名称和时长
+    fn show_preset_editor(&mut self, ui: &mut egui::Ui) {
+        use egui::{Button, ComboBox, Sense, Stroke, TextEdit};
+
+        let mut rects = Vec::with_capacity(self.presets.len());
+        ui.horizontal_wrapped(|ui| {
+            for (index, preset) in self.presets.iter().enumerate() {
+                let label = format!("{}（{}）", preset.name, preset.input);
+                let mut button = Button::new(label).sense(Sense::click_and_drag());
+                if let Some(color) = preset.color {
+                    button = button.stroke(Stroke::new(2.0, color_from_array(color)));
+                }
+                let response = ui
+                    .add(button)
+                    .on_hover_text("拖动调整顺序，双击编辑");
+                if response.drag_started() {
+                    self.preset_drag = Some(index);
+                }
+                if response.double_clicked() {
+                    self.preset_edit = Some(PresetEdit {
+                        index,
+                        name: preset.name.clone(),
+                        input: preset.input.clone(),
+                        alert_sound: preset.alert_sound,
+                        color: preset.color,
+                        tags: preset.tags.join(", "),
+                        start_sound: preset.start_sound,
+                        alarm_pattern: preset.alarm_pattern,
+                        ambient: preset.ambient.clone(),
+                    });
+                }
+                rects.push(response.rect);
+            }
+        });
+
+        if let Some(from) = self.preset_drag {
+            let pointer = ui.ctx().pointer_interact_pos();
+            let target = pointer.and_then(|pos| rects.iter().position(|r| r.contains(pos)));
+            if let Some(to) = target.filter(|&to| to != from) {
+                let stroke = Stroke::new(2.0, ui.visuals().selection.bg_fill);
+                ui.painter().rect_stroke(rects[to].expand(2.0), 4.0, stroke);
+            }
+            if ui.input(|i| i.pointer.any_released()) {
+                self.preset_drag = None;
+                if let Some(to) = target.filter(|&to| to != from) {
+                    let preset = self.presets.remove(from);
+                    self.presets.insert(to, preset);
+                    self.preset_edit = None;
+                    self.save_data();
+                }
+            }
+        }
+
+        let (mut close_edit, mut cancel_edit) = (false, false);
+        let palette = self.palette();
+        if let Some(edit) = &mut self.preset_edit {
+            ui.horizontal(|ui| {
+                ui.label("名称:");
+                ui.add(TextEdit::singleline(&mut edit.name).desired_width(100.0));
+                ui.label("时长:");
+                ui.add(TextEdit::singleline(&mut edit.input).desired_width(80.0));
+                ui.checkbox(&mut edit.alert_sound, "响铃");
+                optional_color_edit(ui, &mut edit.color, "按钮和任务卡片使用的颜色");
+                ui.label("标签:");
+                ui.add(TextEdit::singleline(&mut edit.tags).desired_width(80.0));
+                let name = edit.name.trim();
+                let valid_input = Self::parse_duration(&edit.input).is_some_and(|d| !d.is_zero());
+                let duplicate = self
+                    .presets
+                    .iter()
+                    .enumerate()
+                    .any(|(i, p)| i != edit.index && p.name == name);
+                if ui
+                    .add_enabled(!name.is_empty() && valid_input && !duplicate, Button::new("保存"))
+                    .clicked()
+                    && let Some(preset) = self.presets.get_mut(edit.index)
+                {
+                    preset.name = name.to_string();
+                    preset.input = edit.input.trim().to_string();
+                    preset.alert_sound = edit.alert_sound;
+                    preset.color = edit.color;
+                    preset.tags = parse_tags(&edit.tags);
+                    preset.start_sound = edit.start_sound;
+                    preset.alarm_pattern = edit.alarm_pattern;
+                    preset.ambient = edit.ambient.clone();
+                    close_edit = true;
+                }
+                if ui.button("删除").clicked() && edit.index < self.presets.len() {
+                    self.presets.remove(edit.index);
+                    close_edit = true;
+                }
+                if ui.button("取消").clicked() {
+                    cancel_edit = true;
+                }
+                if !valid_input {
+                    error_label(ui, palette, "时长格式不正确");
+                } else if duplicate {
+                    error_label(ui, palette, "已有同名预设");
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut edit.start_sound, "开始提示音");
+                ui.label("结束提醒音:");
+                let selected = match edit.alarm_pattern {
+                    None => "跟随设置",
+                    Some(pattern) => ALARM_PATTERNS
+                        .iter()
+                        .find(|(_, p)| *p == pattern)
+                        .map_or("自定义", |(name, _)| name),
+                };
+                ComboBox::from_id_source("preset_alarm_pattern")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut edit.alarm_pattern, None, "跟随设置");
+                        for (name, pattern) in ALARM_PATTERNS {
+                            ui.selectable_value(&mut edit.alarm_pattern, Some(pattern), name);
+                        }
+                    });
+                ui.label("背景音:");
+                ambient_picker(ui, "preset_ambient", &mut edit.ambient);
+            });
+        }
+        if close_edit || cancel_edit {
+            self.preset_edit = None;
+        }
+        if close_edit {
+            self.save_data();
+        }
+    }
+
+    /// 应用“编辑任务”窗口中的修改；秒表和闹钟只能改名。新时长必须长于已经过去的时间
+    fn apply_task_edit(&mut self) {
+        let Some(edit) = &mut self.task_edit else {
+            return;
+        };
+        let Some(task) = self.tasks.iter_mut().find(|t| t.id == edit.id && !t.is_finished()) else {
+            self.task_edit = None;
+            return;
+        };
+        let name = edit.name.trim();
+        if name.is_empty() {
+            edit.error = Some("任务名不能为空".to_string());
+            return;
+        }
+        if task.kind == TaskKind::Countdown && edit.input.trim() != task.input {
+            let Some(duration) = Self::parse_duration(&edit.input) else {
+                edit.error = Some("时长格式不正确".to_string());
+                return;
+            };
+            let elapsed = task.elapsed();
+            if duration <= elapsed {
+                edit.error = Some(format!("新时长必须长于已经过去的 {}", format_hms(elapsed, true)));
+                return;
+            }
+            task.set_duration(duration, edit.input.trim().to_string());
+        }
+        task.name = name.to_string();
+        task.tags = parse_tags(&edit.tags);
+        let alarm = matches!(task.kind, TaskKind::Alarm { .. });
+        self.task_edit = None;
+        if alarm {
+            self.save_data();
+        }
+    }
+
+    /// 把第 `from` 个任务移到第 `to` 个位置，分组标题随之调整，其他任务不会换组。
+    /// 列表顺序只对会保存的闹钟跨重启保留（按列表顺序写入），所以移动后保存一次；
+    /// 进行中的倒计时本来就不保存，也就没有单独的排序字段
+    fn move_task(&mut self, from: usize, to: usize) {
+        let task = self.tasks.remove(from);
+        self.tasks.insert(to, task);
+        Self::shift_headers_on_move(&mut self.task_headers, from, to);
+        self.save_data();
+    }
+
+    /// 任务从 `from` 移到 `to` 后调整分组标题的位置，让中间被挤动的任务留在原来的分组里
+    fn shift_headers_on_move(headers: &mut [TaskHeader], from: usize, to: usize) {
+        for header in headers {
+            if from < to && header.position > from && header.position <= to {
+                header.position -= 1;
+            } else if to < from && header.position > to && header.position <= from {
+                header.position += 1;
+            }
+        }
+    }
+
+    /// 手动删除或停止任务：先记入撤销栈，再确认并从列表移除。
+    /// 已结束的任务按已确认记下，撤销后不会再次提醒；进行中的任务保持未确认，结束时照常提醒
+    fn delete_tasks(&mut self, ids: &[usize]) {
+        let removed: Vec<(usize, CountdownTask)> = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| ids.contains(&t.id))
+            .map(|(i, t)| {
+                let mut task = t.clone();
+                if task.finished_at.is_some() {
+                    task.acknowledged = true;
+                }
+                (i, task)
+            })
+            .collect();
+        self.push_undo(UndoEntry::Tasks(removed, self.next_task_id, self.task_headers.clone()));
+        for &id in ids {
+            self.acknowledge_task(id);
+        }
+        self.remove_tasks(ids);
+    }
+
+    /// 删除历史记录，可以撤销
+    fn delete_history(&mut self, ids: &[usize]) {
+        let removed: Vec<(usize, CountdownTask)> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| ids.contains(&t.id))
+            .map(|(i, t)| (i, t.clone()))
+            .collect();
+        self.push_undo(UndoEntry::History(removed));
+        self.history.retain(|t| !ids.contains(&t.id));
+        self.history_selected.retain(|id| !ids.contains(id));
+        self.save_data();
+    }
+
+    fn push_undo(&mut self, entry: UndoEntry) {
+        let (UndoEntry::Tasks(removed, _, _) | UndoEntry::History(removed)) = &entry;
+        if removed.is_empty() {
+            return;
+        }
+        if self.undo_stack.len() >= UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(entry);
+        self.undo_toast = Some(Instant::now());
+    }
+
+    /// 撤销最近一次删除，条目放回原来的位置。停止的任务按原来的开始时刻继续计时；
+    /// 停止重复计时时提前安排的下一次随之撤回，免得同一个重复计时同时有两份
+    fn undo_delete(&mut self) {
+        let Some(entry) = self.undo_stack.pop() else {
+            return;
+        };
+        match entry {
+            UndoEntry::Tasks(removed, first_new_id, headers) => {
+                let skipped: Vec<usize> = removed
+                    .iter()
+                    .filter(|(_, t)| t.finished_at.is_none())
+                    .filter_map(|(_, t)| t.recurring)
+                    .collect();
+                let scheduled: Vec<usize> = self
+                    .tasks
+                    .iter()
+                    .filter(|t| t.id >= first_new_id && !t.is_finished())
+                    .filter(|t| t.recurring.is_some_and(|id| skipped.contains(&id)))
+                    .map(|t| t.id)
+                    .collect();
+                self.remove_tasks(&scheduled);
+                Self::reinsert_tasks(&mut self.tasks, &mut self.task_headers, removed, headers);
+            }
+            UndoEntry::History(removed) => {
+                for (index, task) in removed {
+                    self.history.insert(index.min(self.history.len()), task);
+                }
+            }
+        }
+        self.undo_toast = None;
+        self.save_data();
+    }
+
+    /// 从任务列表移除任务，分组标题随之前移，保持在原来的任务之间
+    fn remove_tasks(&mut self, ids: &[usize]) {
+        Self::collapse_headers(&self.tasks, &mut self.task_headers, ids);
+        let alarm_removed = self
+            .tasks
+            .iter()
+            .any(|t| ids.contains(&t.id) && matches!(t.kind, TaskKind::Alarm { .. }));
+        self.tasks.retain(|t| !ids.contains(&t.id));
+        if alarm_removed {
+            self.save_data();
+        }
+    }
+
+    /// 保存用的分组标题：进行中的倒计时不保存，位置改为数它前面有几个会保存的任务，
+    /// 重启后恢复的闹钟按原来的顺序排在最前，标题仍落在原来的任务之间
+    fn headers_for_saving(tasks: &[CountdownTask], headers: &[TaskHeader]) -> Vec<TaskHeader> {
+        headers
+            .iter()
+            .map(|header| TaskHeader {
+                title: header.title.clone(),
+                position: tasks[..header.position.min(tasks.len())]
+                    .iter()
+                    .filter(|t| t.is_saved_pending())
+                    .count(),
+            })
+            .collect()
+    }
+
+    /// 移除 `ids` 中的任务前调整分组标题的位置，按剩下的任务重新计数
+    fn collapse_headers(tasks: &[CountdownTask], headers: &mut [TaskHeader], ids: &[usize]) {
+        for header in headers {
+            header.position = tasks[..header.position.min(tasks.len())]
+                .iter()
+                .filter(|t| !ids.contains(&t.id))
+                .count();
+        }
+    }
+
+    /// 把撤销的任务放回原来的下标。分组标题在删除后没有增删时恢复删除前的位置，
+    /// 否则只把原下标之后的标题顺延
+    fn reinsert_tasks(
+        tasks: &mut Vec<CountdownTask>,
+        headers: &mut [TaskHeader],
+        removed: Vec<(usize, CountdownTask)>,
+        saved: Vec<TaskHeader>,
+    ) {
+        let unchanged = headers.len() == saved.len() && headers.iter().zip(&saved).all(|(a, b)| a.title == b.title);
+        for (index, task) in removed {
+            if !unchanged {
+                for header in headers.iter_mut() {
+                    if header.position > index {
+                        header.position += 1;
+                    }
+                }
+            }
+            tasks.insert(index.min(tasks.len()), task);
+        }
+        if unchanged {
+            for (header, saved) in headers.iter_mut().zip(saved) {
+                header.position = saved.position.min(tasks.len());
+            }
+        }
+    }
+
+    /// 在任务列表末尾添加分组标题，之后添加的任务显示在它下面
+    fn add_task_header(&mut self) {
+        let title = self.header_input.trim();
+        if title.is_empty() {
+            return;
+        }
+        self.task_headers.push(TaskHeader {
+            title: title.to_string(),
+            position: self.tasks.len(),
+        });
+        self.header_input.clear();
+        self.save_data();
+    }
+
+    /// 显示位于第 `slot` 个任务之前的分组标题；双击标题可重命名
+    fn show_task_headers(
+        ui: &mut egui::Ui,
+        headers: &mut [TaskHeader],
+        slot: usize,
+        task_count: usize,
+        edit: &mut Option<(usize, String)>,
+    ) -> Option<HeaderAction> {
+        use egui::{RichText, Sense};
+
+        let mut action = None;
+        for (index, header) in headers.iter_mut().enumerate() {
+            if header.position.min(task_count) != slot {
+                continue;
+            }
+            ui.horizontal(|ui| {
+                match edit {
+                    Some((editing, title)) if *editing == index => {
+                        let response = ui.text_edit_singleline(title);
+                        if response.lost_focus() {
+                            if !title.trim().is_empty() {
+                                header.title = title.trim().to_string();
+                            }
+                            *edit = None;
+                        } else {
+                            response.request_focus();
+                        }
+                    }
+                    _ => {
+                        let label = ui
+                            .add(egui::Label::new(RichText::new(&header.title).heading()).sense(Sense::click()))
+                            .on_hover_text("双击重命名");
+                        if label.double_clicked() {
+                            *edit = Some((index, header.title.clone()));
+                        }
+                    }
+                }
+                if ui.add_enabled(slot > 0, egui::Button::new("⬆").small()).clicked() {
+                    action = Some(HeaderAction::MoveUp(index));
+                }
+                if ui.add_enabled(slot < task_count, egui::Button::new("⬇").small()).clicked() {
+                    action = Some(HeaderAction::MoveDown(index));
+                }
+                if ui.small_button("✕").on_hover_text("删除分组标题").clicked() {
+                    action = Some(HeaderAction::Remove(index));
+                }
+            });
+            ui.separator();
+        }
+        action
+    }
+
+    fn apply_header_action(&mut self, action: HeaderAction) {
+        let task_count = self.tasks.len();
+        match action {
+            HeaderAction::Remove(index) => {
+                self.task_headers.remove(index);
+                self.header_edit = None;
+            }
+            HeaderAction::MoveUp(index) => {
+                let header = &mut self.task_headers[index];
+                header.position = header.position.min(task_count).saturating_sub(1);
+            }
+            HeaderAction::MoveDown(index) => {
+                let header = &mut self.task_headers[index];
+                header.position = (header.position + 1).min(task_count);
+            }
+        }
+        self.save_data();
+    }
+
+    /// 把已结束的任务推迟：以原任务名新建一个稍后提醒的倒计时，并从列表移除原任务（历史记录保留）
+    fn snooze_task(&mut self, id: usize) {
+        let Some(index) = self.tasks.iter().position(|t| t.id == id) else {
+            return;
+        };
+        let original = self.tasks[index].clone();
+        self.acknowledge_task(id);
+        self.remove_tasks(&[id]);
+        let new_id = self.next_task_id;
+        self.next_task_id += 1;
+        let mut task = CountdownTask::new(
+            new_id,
+            original.name.clone(),
+            format!("{}:00", self.snooze_minutes),
+            Duration::from_secs(self.snooze_minutes * 60),
+        );
+        task.source = TaskSource::Snooze;
+        task.inherit_options(&original);
+        task.snoozed_from = Some(original.snoozed_from.unwrap_or(original.id));
+        task.snooze_count = original.snooze_count + 1;
+        self.tasks.push(task);
+        if self.show_finished_popup == Some(id) {
+            self.show_finished_popup = None;
+        }
+    }
+
+    /// 推迟所有已结束但尚未确认的任务，并停止正在响的提醒；重要任务不受影响，仍需逐个确认
+    fn snooze_all(&mut self) {
+        let ids: Vec<usize> = self
+            .tasks
+            .iter()
+            .filter(|t| t.finished_at.is_some() && !t.acknowledged && !t.important)
+            .map(|t| t.id)
+            .collect();
+        for id in ids {
+            self.snooze_task(id);
+        }
+        self.stop_all_sounds();
+        if !self.popup_pinned() {
+            self.show_finished_popup = None;
+        }
+    }
+
+    /// 当前的结束弹窗属于尚未确认的重要任务，不能被其他任务的弹窗替换或被批量操作关闭
+    fn popup_pinned(&self) -> bool {
+        self.show_finished_popup
+            .is_some_and(|id| self.tasks.iter().any(|t| t.id == id && t.important && !t.acknowledged))
+    }
+
+    /// 把单个任务的完整信息导出为 JSON，路径为空时使用 task_<id>.json
+    fn export_task(&mut self, task: &CountdownTask) {
+        let path = match self.task_file_path.trim() {
+            "" => format!("task_{}.json", task.id),
+            path => path.to_string(),
+        };
+        let result = serde_json::to_string_pretty(task)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+        self.task_file_status = Some(match result {
+            Ok(()) => format!("已导出到 {}", path),
+            Err(e) => format!("导出失败: {}", e),
+        });
+    }
+
+    /// 导入单个任务：已结束的任务放入历史记录，未结束的任务以相同设置重新开始
+    fn import_task(&mut self) {
+        let path = self.task_file_path.trim();
+        if path.is_empty() {
+            self.task_file_status = Some("请先填写文件路径".to_string());
+            return;
+        }
+        let result = fs::read_to_string(path)
+            .map_err(|e| format!("读取失败: {}", e))
+            .and_then(|json| {
+                serde_json::from_str::<CountdownTask>(&json).map_err(|e| format!("文件格式错误: {}", e))
+            });
+        let imported = match result {
+            Ok(task) if task.duration.is_zero() => {
+                self.task_file_status = Some("导入失败: 任务时长为 0".to_string());
+                return;
+            }
+            Ok(task) => task,
+            Err(msg) => {
+                self.task_file_status = Some(msg);
+                return;
+            }
+        };
+
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        if imported.finished_at.is_some() {
+            let mut task = imported;
+            task.id = id;
+            task.acknowledged = true;
+            self.history.push(task);
+            self.trim_history();
+            self.save_data();
+            self.task_file_status = Some("已导入到历史记录".to_string());
+        } else {
+            let mut task =
+                CountdownTask::new(id, imported.name.clone(), imported.input.clone(), imported.duration);
+            task.source = imported.source;
+            task.inherit_options(&imported);
+            task.locked = imported.locked;
+            self.tasks.push(task);
+            self.task_file_status = Some("已作为新任务开始".to_string());
+        }
+    }
+
+    /// 把另一份数据文件中的历史记录合并进来，只读取其历史，不改动任何设置。
+    /// 名称和开始时间都相同的记录视为重复；编号冲突的记录重新分配编号。
+    fn merge_history_from(&mut self) {
+        let path = self.history_merge_path.trim();
+        if path.is_empty() {
+            self.history_merge_status = Some("请先填写数据文件路径".to_string());
+            return;
+        }
+        if Path::new(path) == Path::new(self.data_path()) {
+            self.history_merge_status = Some("不能从当前数据文件导入".to_string());
+            return;
+        }
+        // 旧版本数据文件缺少的字段由 serde 默认值补齐
+        let result = fs::read_to_string(path)
+            .map_err(|e| format!("读取失败: {}", e))
+            .and_then(|json| {
+                serde_json::from_str::<PersistentData>(&json).map_err(|e| format!("文件格式错误: {}", e))
+            });
+        let source = match result {
+            Ok(persist) => persist.history,
+            Err(msg) => {
+                self.history_merge_status = Some(msg);
+                return;
+            }
+        };
+
+        let mut added = 0;
+        let mut skipped = 0;
+        for mut task in source {
+            let duplicate = self
+                .history
+                .iter()
+                .any(|t| t.name == task.name && t.created_at == task.created_at);
+            if duplicate {
+                skipped += 1;
+                continue;
+            }
+            if self.history.iter().any(|t| t.id == task.id) || task.id >= self.next_task_id {
+                task.id = self.next_task_id;
+                self.next_task_id += 1;
+            }
+            task.acknowledged = true;
+            self.history.push(task);
+            added += 1;
+        }
+        if added > 0 {
+            // 保持按时间先后排列，超出上限时裁掉的仍是最早的记录；
+            // 旧版本不保存结束时间，这样的记录按开始时间排
+            self.history.sort_by_key(|t| t.finished_at.unwrap_or(t.created_at));
+            self.trim_history();
+            self.save_data();
+        }
+        self.history_merge_status = Some(format!("已导入 {} 条，跳过 {} 条重复的记录", added, skipped));
+    }
+
+    /// 把结束提醒中填写的结果追加到任务和对应历史记录的备注上，未填写时什么也不做
+    fn save_finish_note(&mut self, id: usize) {
+        let note = self.finish_note.trim().to_string();
+        self.finish_note.clear();
+        self.finish_note_id = None;
+        if note.is_empty() {
+            return;
+        }
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+            task.add_outcome(&note);
+        }
+        if let Some(entry) = self.history.iter_mut().find(|t| t.id == id) {
+            entry.add_outcome(&note);
+            self.save_data();
+        }
+    }
+
+    /// 一次删除所有勾选的历史记录
+    fn delete_selected_history(&mut self) {
+        let ids: Vec<usize> = self.history_selected.drain().collect();
+        self.delete_history(&ids);
+        self.confirm_history_delete = false;
+    }
+
+    /// 按保留数量删除最早的历史记录
+    fn trim_history(&mut self) {
+        if self.history_limit > 0 && self.history.len() > self.history_limit {
+            let excess = self.history.len() - self.history_limit;
+            self.history.drain(..excess);
+        }
+    }
+
+    /// 汇总所有进行中计时的一行纯文字状态，便于屏幕阅读器朗读和复制
+    fn status_line(&self) -> String {
+        let active: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|t| !t.is_finished())
+            .map(|t| {
+                let paused = if t.paused { "（已暂停）" } else { "" };
+                let verb = if t.kind == TaskKind::Stopwatch { "已用" } else { "剩余" };
+                format!("{}{}{}{}", t.name, verb, format_duration_words(t.shown_time()), paused)
+            })
+            .collect();
+        if active.is_empty() {
+            "没有进行中的计时".to_string()
+        } else {
+            format!("{}个计时进行中：{}", active.len(), active.join("，"))
+        }
+    }
+
+    /// 每隔几秒检查一次系统空闲时间，空闲超过阈值时暂停运行中的任务，回来后继续
+    #[cfg(feature = "idle-detection")]
+    fn check_idle(&mut self) {
+        if !self.idle_pause || !self.idle_supported {
+            return;
+        }
+        if self
+            .last_idle_poll
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(2))
+        {
+            return;
+        }
+        self.last_idle_poll = Some(Instant::now());
+        let Some(idle) = idle::system_idle_time() else {
+            return;
+        };
+
+        if idle >= Duration::from_secs(self.idle_threshold_secs) {
+            for task in &mut self.tasks {
+                if !task.paused && !task.is_finished() {
+                    // 从开始空闲时算起，离开的这段时间不计入任务用时
+                    task.pause_for(idle);
+                    // 闹钟按墙钟计时，不会暂停，也就不必记下
+                    if task.paused {
+                        self.idle_paused_ids.push(task.id);
+                    }
+                }
+            }
+        } else if !self.idle_paused_ids.is_empty() {
+            for task in &mut self.tasks {
+                if self.idle_paused_ids.contains(&task.id) {
+                    task.resume();
+                }
+            }
+            self.idle_paused_ids.clear();
+        }
+    }
+
+    /// 启动后检查固定的显示器是否还在：窗口没有停在固定的位置，或所在显示器的尺寸、缩放比例
+    /// 与固定时不同，说明原显示器已断开，回到主显示器居中
+    fn check_pinned_monitor(&mut self, frame: &mut Frame) {
+        if self.center_on_primary {
+            // 上一帧已移到主显示器，现在的“当前显示器”就是主显示器
+            self.center_on_primary = false;
+            frame.set_centered();
+        }
+        if self.monitor_checked {
+            return;
+        }
+        self.monitor_checked = true;
+        let (Some([w, h]), Some([x, y])) = (self.pinned_monitor_size, self.pinned_window_pos) else {
+            return;
+        };
+        let info = frame.info();
+        let same_size = info
+            .window_info
+            .monitor_size
+            .is_some_and(|size| (size.x - w).abs() < 1.0 && (size.y - h).abs() < 1.0);
+        let same_scale = match (self.pinned_monitor_scale, info.native_pixels_per_point) {
+            (Some(pinned), Some(scale)) => (pinned - scale).abs() < 0.01,
+            // 旧数据没有记录缩放比例
+            _ => true,
+        };
+        // 窗口管理器可能因边框把窗口挪动几个像素，位置相差不大时仍算原位
+        let in_place = info
+            .window_info
+            .position
+            .is_none_or(|pos| (pos.x - x).abs() < 16.0 && (pos.y - y).abs() < 16.0);
+        if !(same_size && same_scale && in_place) {
+            // 坐标以主显示器左上角为原点
+            frame.set_window_pos(egui::pos2(0.0, 0.0));
+            self.center_on_primary = true;
+            self.monitor_status = Some("固定的显示器不可用，已回到主显示器".to_string());
+        }
+    }
+
+    fn pin_to_current_monitor(&mut self, frame: &Frame) {
+        let info = frame.info();
+        match (info.window_info.position, info.window_info.monitor_size) {
+            (Some(pos), Some(size)) => {
+                self.pinned_window_pos = Some([pos.x, pos.y]);
+                self.pinned_monitor_size = Some([size.x, size.y]);
+                self.pinned_monitor_scale = info.native_pixels_per_point;
+                self.monitor_status = Some("已固定到当前显示器".to_string());
+                self.save_data();
+            }
+            _ => self.monitor_status = Some("无法获取窗口所在的显示器".to_string()),
+        }
+    }
+
+    /// 进行中的任务列表（含分组标题），居中布局和侧栏布局共用
+    fn show_task_list(&mut self, ui: &mut egui::Ui, max_height: f32) {
+        use egui::{Button, Key, ProgressBar, RichText, ScrollArea, Stroke, TextEdit};
+
+        let unlocked = |t: &CountdownTask| !t.locked && !t.is_finished();
+        // 闹钟不能暂停，不计入
+        let running = self
+            .tasks
+            .iter()
+            .filter(|t| unlocked(t) && !t.paused && !matches!(t.kind, TaskKind::Alarm { .. }))
+            .count();
+        let paused = self
+            .tasks
+            .iter()
+            .filter(|t| unlocked(t) && t.paused && t.scheduled_start.is_none())
+            .count();
+        let stoppable = self.tasks.iter().filter(|t| unlocked(t)).count();
+        let tags = collect_tags(&self.tasks);
+        if self.task_tag_filter.as_ref().is_some_and(|tag| !tags.contains(tag)) {
+            self.task_tag_filter = None;
+        }
+        if !tags.is_empty() {
+            tag_filter_bar(ui, &tags, &mut self.task_tag_filter);
+        }
+        if stoppable > 0 {
+            let enabled = self.global_freeze.is_none();
+            ui.horizontal(|ui| {
+                if ui.add_enabled(enabled && running > 0, Button::new("全部暂停")).clicked() {
+                    self.pause_all();
+                }
+                if ui.add_enabled(enabled && paused > 0, Button::new("全部继续")).clicked() {
+                    self.resume_all();
+                }
+                if self.confirm_stop_all {
+                    if ui.button(format!("确定停止 {} 个", stoppable)).clicked() {
+                        self.stop_all();
+                        self.confirm_stop_all = false;
+                    }
+                    if ui.button("取消").clicked() {
+                        self.confirm_stop_all = false;
+                    }
+                } else if ui
+                    .add_enabled(enabled, Button::new("全部停止"))
+                    .on_hover_text("已锁定的任务不受影响")
+                    .clicked()
+                {
+                    self.confirm_stop_all = true;
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("分组标题:");
+            let response = ui.add(TextEdit::singleline(&mut self.header_input).hint_text("例如 上午"));
+            let enter = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            if ui.button("添加").on_hover_text("添加到列表末尾，之后添加的任务显示在它下面").clicked() || enter {
+                self.add_task_header();
+            }
+        });
+
+        let mut header_action = None;
+        let mut export_request = None;
+        let mut restart_request = None;
+        let mut duplicate_request = None;
+        let header_edit_before = self.header_edit.is_some();
+        ui.push_id("countdown_tasks", |ui| {
+            ScrollArea::vertical().max_height(max_height).show(ui, |ui| {
+                let mut remove_ids = Vec::new();
+                let mut stopwatch_stop_ids = Vec::new();
+                let mut recurring_skips = Vec::new();
+                let mut alarm_moved = false;
+                let task_count = self.tasks.len();
+
+                let palette = self.palette();
+                // 重要任务置顶，其后依次是高、普通、低优先级；普通任务按原顺序穿插分组标题
+                let tasks = &self.tasks;
+                let filter = self.task_tag_filter.as_ref();
+                let order: Vec<usize> = (0..4)
+                    .flat_map(|group| (0..task_count).filter(move |&i| tasks[i].list_group() == group))
+                    .filter(|&i| filter.is_none_or(|tag| tasks[i].tags.contains(tag)))
+                    .collect();
+                let mut header_slot = 0;
+                let mut card_rects = Vec::with_capacity(task_count);
+                let mut drag_started = None;
+                for slot in order {
+                    let task = &mut self.tasks[slot];
+                    while task.list_group() == 2 && header_slot <= slot {
+                        if let Some(action) = Self::show_task_headers(
+                            ui,
+                            &mut self.task_headers,
+                            header_slot,
+                            task_count,
+                            &mut self.header_edit,
+                        ) {
+                            header_action = Some(action);
+                        }
+                        header_slot += 1;
+                    }
+                    let (remain, progress) = task.displayed();
+                    let step_color = task
+                        .chain
+                        .as_ref()
+                        .and_then(|link| link.step()?.color)
+                        .or(task.color)
+                        .map(color_from_array);
+                    let accent = step_color.or_else(|| self.progress_color.then(|| progress_accent(progress, palette)));
+                    let mut card = egui::Frame::group(ui.style());
+                    if let Some(color) = accent {
+                        card = card.stroke(Stroke::new(2.0, color));
+                    }
+                    if task.priority == Priority::High {
+                        card = card.stroke(Stroke::new(2.5, palette.alert));
+                    }
+                    // 提前提醒后闪烁几下
+                    if let Some(at) = task.lead_flash {
+                        let elapsed = at.elapsed();
+                        if elapsed >= LEAD_FLASH {
+                            task.lead_flash = None;
+                        } else if elapsed.as_millis() / 250 % 2 == 0 {
+                            card = card
+                                .stroke(Stroke::new(2.0, palette.warning))
+                                .fill(palette.warning.gamma_multiply(0.25));
+                        }
+                    }
+                    if self.added_highlight.is_some_and(|(id, _)| id == task.id) {
+                        let selection = ui.visuals().selection.bg_fill;
+                        card = card.stroke(Stroke::new(2.0, selection)).fill(selection.gamma_multiply(0.3));
+                    }
+
+                    let card_response = card.show(ui, |ui| {
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                let handle = ui
+                                    .add(egui::Label::new("☰").sense(egui::Sense::drag()))
+                                    .on_hover_text("拖动调整顺序");
+                                if handle.drag_started() {
+                                    drag_started = Some(task.id);
+                                }
+                                let star = if task.important { "⭐" } else { "☆" };
+                                ui.toggle_value(&mut task.important, star)
+                                    .on_hover_text("重要任务置顶显示，结束提醒更响，且必须手动确认");
+                                if ui
+                                    .small_button(task.priority.short())
+                                    .on_hover_text(format!(
+                                        "{}（点击切换）\n高优先级排在前面、带彩色边框，结束提醒在确认前反复响起；低优先级排在最后",
+                                        task.priority.label()
+                                    ))
+                                    .clicked()
+                                {
+                                    task.priority = task.priority.next();
+                                }
+                                let name = RichText::new(format!("任务名: {}", task.name));
+                                ui.label(if task.priority == Priority::Low { name.weak() } else { name.strong() });
+                                for tag in &task.tags {
+                                    ui.label(RichText::new(format!("#{}", tag)).small().color(tag_color(tag)));
+                                }
+                            });
+                            // 进行中可以随时补充备注；结束后只读，历史记录里保存的是结束时的内容
+                            let title = match task.note.lines().next() {
+                                Some(first) => format!("备注: {}", first),
+                                None => "备注".to_string(),
+                            };
+                            egui::CollapsingHeader::new(title)
+                                .id_source(("task_note", task.id))
+                                .show(ui, |ui| {
+                                    if task.is_finished() {
+                                        ui.label(&task.note);
+                                    } else {
+                                        let response = ui.add(
+                                            TextEdit::multiline(&mut task.note)
+                                                .hint_text("任务备注")
+                                                .desired_rows(3),
+                                        );
+                                        // 闹钟会保存到数据文件，改完备注后保存一次
+                                        if response.lost_focus() && matches!(task.kind, TaskKind::Alarm { .. }) {
+                                            alarm_moved = true;
+                                        }
+                                    }
+                                });
+                            ui.label(RichText::new(
+                                format!("开始时间: {}", task.created_at.format("%Y-%m-%d %H:%M:%S")),
+                            ));
+                            let stopwatch = task.kind == TaskKind::Stopwatch;
+                            let alarm = matches!(task.kind, TaskKind::Alarm { .. });
+                            if alarm {
+                                let label = if task.special { "目标时间" } else { "响铃时间" };
+                                ui.label(format!("{}: {}", label, task.input));
+                            } else if !stopwatch {
+                                ui.label(format!("设定时长: {}", task.input));
+                            }
+
+                            ui.horizontal(|ui| {
+                                if task.special {
+                                    ui.label(RichText::new(format_days_hms(remain, self.compact_time)).size(32.0).strong());
+                                } else if stopwatch {
+                                    ui.label(format!("已用时间: {}", format_hms(remain, self.compact_time)));
+                                } else if task.count_overtime && task.is_finished() {
+                                    let overtime = task.overtime.unwrap_or_else(|| task.overtime_now());
+                                    ui.colored_label(
+                                        palette.error,
+                                        format!("超时: -{}", format_hms(overtime, self.compact_time)),
+                                    );
+                                } else {
+                                    ui.label(format!("剩余时间: {}", format_days_hms(remain, self.compact_time)));
+                                }
+                                if !stopwatch {
+                                    let mut bar = ProgressBar::new(progress).show_percentage();
+                                    if let Some(color) = accent {
+                                        bar = bar.fill(color);
+                                    }
+                                    ui.add(bar);
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                if task.is_finished() {
+                                    if ui.button("删除").clicked() {
+                                        remove_ids.push(task.id);
+                                    }
+                                    if Self::can_restart(task) && ui.button("重新开始").clicked() {
+                                        restart_request = Some(task.clone());
+                                    }
+                                    Self::show_overrun_badge(ui, task, self.compact_time, palette);
+                                } else {
+                                    let unlocked = !task.locked && self.global_freeze.is_none();
+                                    if let Some(at) = task.scheduled_start {
+                                        ui.label(format!("⏳ 等待 {} 开始", at.format("%H:%M")));
+                                        if ui.add_enabled(unlocked, Button::new("立即开始")).clicked() {
+                                            task.resume();
+                                        }
+                                    } else if task.paused {
+                                        if ui.add_enabled(unlocked, Button::new("继续")).clicked() {
+                                            task.resume();
+                                        }
+                                    } else if !alarm && ui.add_enabled(unlocked, Button::new("暂停")).clicked() {
+                                        task.pause();
+                                    }
+
+                                    if ui.add_enabled(unlocked, Button::new("停止")).clicked() {
+                                        if stopwatch {
+                                            stopwatch_stop_ids.push(task.id);
+                                        } else {
+                                            remove_ids.push(task.id);
+                                        }
+                                        // 停止重复计时的这一次等于跳过，直接安排下一次
+                                        if let Some(id) = task.recurring {
+                                            let after = match task.kind {
+                                                TaskKind::Alarm { at } => at,
+                                                _ => Local::now(),
+                                            };
+                                            recurring_skips.push((id, after));
+                                        }
+                                    }
+
+                                    if ui
+                                        .add_enabled(unlocked, Button::new("编辑"))
+                                        .on_hover_text("修改任务名和设定时长")
+                                        .clicked()
+                                    {
+                                        self.task_edit = Some(TaskEdit {
+                                            id: task.id,
+                                            name: task.name.clone(),
+                                            input: task.input.clone(),
+                                            tags: task.tags.join(", "),
+                                            error: None,
+                                        });
+                                    }
+                                    if Self::can_restart(task)
+                                        && ui.button("复制").on_hover_text("再开一个相同的任务，从现在开始计时").clicked()
+                                    {
+                                        duplicate_request = Some(task.clone());
+                                    }
+
+                                    if stopwatch {
+                                        if ui.add_enabled(!task.paused, Button::new("计次")).clicked() {
+                                            task.laps.push(task.elapsed());
+                                        }
+                                    } else {
+                                        for &minutes in &self.extend_minutes {
+                                            if ui
+                                                .small_button(format!("+{}分", minutes))
+                                                .on_hover_text("延长设定时长")
+                                                .clicked()
+                                            {
+                                                task.extend(Duration::from_secs(minutes * 60));
+                                                // 推迟后的闹钟时间要写入数据文件
+                                                alarm_moved |= alarm;
+                                            }
+                                        }
+                                    }
+
+                                    let mut frozen = task.frozen_display.is_some();
+                                    if ui
+                                        .toggle_value(&mut frozen, "冻结显示")
+                                        .on_hover_text("固定显示当前剩余时间，计时仍在后台继续")
+                                        .changed()
+                                    {
+                                        task.frozen_display = frozen.then(|| (task.shown_time(), task.progress()));
+                                    }
+
+                                    let lock_label = if task.locked { "🔒 已锁定" } else { "🔓 锁定" };
+                                    ui.toggle_value(&mut task.locked, lock_label)
+                                        .on_hover_text("锁定后需先解锁才能暂停或停止");
+                                }
+
+                                if ui.button("导出").on_hover_text("将此任务导出为 JSON 文件").clicked() {
+                                    export_request = Some(task.clone());
+                                }
+                            });
+
+                            // 最近的计次在上，每行为单圈用时和累计用时
+                            let mut previous = Duration::ZERO;
+                            let laps: Vec<(Duration, Duration)> = task
+                                .laps
+                                .iter()
+                                .map(|&total| {
+                                    let split = total.saturating_sub(previous);
+                                    previous = total;
+                                    (split, total)
+                                })
+                                .collect();
+                            for (index, (split, total)) in laps.iter().enumerate().rev() {
+                                ui.label(format!(
+                                    "第 {} 圈  {}  累计 {}",
+                                    index + 1,
+                                    format_hms(*split, self.compact_time),
+                                    format_hms(*total, self.compact_time)
+                                ));
+                            }
+                        });
+                    });
+                    if self.scroll_to_task == Some(task.id) {
+                        card_response.response.scroll_to_me(Some(egui::Align::Center));
+                        self.scroll_to_task = None;
+                    }
+                    card_rects.push((slot, task.list_group(), card_response.response.rect));
+
+                    ui.add_space(10.0);
+                }
+
+                while header_slot <= task_count {
+                    if let Some(action) = Self::show_task_headers(
+                        ui,
+                        &mut self.task_headers,
+                        header_slot,
+                        task_count,
+                        &mut self.header_edit,
+                    ) {
+                        header_action = Some(action);
+                    }
+                    header_slot += 1;
+                }
+
+                if drag_started.is_some() {
+                    self.task_drag = drag_started;
+                }
+                // 只能在同一优先级的任务之间拖动，放开时移到目标卡片的位置
+                if let Some(id) = self.task_drag {
+                    let from = self.tasks.iter().position(|t| t.id == id);
+                    let pointer = ui.ctx().pointer_interact_pos();
+                    let target = from.and_then(|from| {
+                        let group = self.tasks[from].list_group();
+                        card_rects
+                            .iter()
+                            .find(|(_, g, rect)| *g == group && pointer.is_some_and(|pos| rect.contains(pos)))
+                            .map(|&(slot, _, rect)| (from, slot, rect))
+                    });
+                    if let Some((_, _, rect)) = target.filter(|&(from, to, _)| from != to) {
+                        let stroke = Stroke::new(2.0, ui.visuals().selection.bg_fill);
+                        ui.painter().rect_stroke(rect.expand(2.0), 4.0, stroke);
+                    }
+                    if from.is_none() || ui.input(|i| i.pointer.any_released()) {
+                        self.task_drag = None;
+                        if let Some((from, to, _)) = target.filter(|&(from, to, _)| from != to) {
+                            self.move_task(from, to);
+                        }
+                    }
+                }
+
+                self.delete_tasks(&remove_ids);
+                for id in stopwatch_stop_ids {
+                    self.stop_stopwatch(id);
+                }
+                for (id, after) in recurring_skips {
+                    if let Some(timer) = self.recurring.iter().find(|r| r.id == id).cloned() {
+                        self.start_recurring(&timer, after);
+                    }
+                }
+                if alarm_moved {
+                    self.save_data();
+                }
+            });
+        });
+        if let Some(action) = header_action {
+            self.apply_header_action(action);
+        } else if header_edit_before && self.header_edit.is_none() {
+            // 重命名结束
+            self.save_data();
+        }
+        if let Some(task) = export_request {
+            self.export_task(&task);
+        }
+        if let Some(task) = restart_request {
+            self.restart_task(&task, TaskSource::Repeat);
+        }
+        if let Some(task) = duplicate_request {
+            self.duplicate_task(&task);
+        }
+    }
+
+    /// 任务列表和历史记录之间的拖动分隔条
+    fn show_split_handle(&mut self, ui: &mut egui::Ui) {
+        use egui::{pos2, vec2, CursorIcon, Sense, Stroke};
+
+        let (rect, response) = ui.allocate_exact_size(vec2(ui.available_width(), 10.0), Sense::drag());
+        let response = response.on_hover_cursor(CursorIcon::ResizeVertical);
+        let stroke = if response.hovered() || response.dragged() {
+            ui.visuals().widgets.active.fg_stroke
+        } else {
+            Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color)
+        };
+        ui.painter().line_segment(
+            [pos2(rect.left(), rect.center().y), pos2(rect.right(), rect.center().y)],
+            stroke,
+        );
+
+        if response.dragged() {
+            let min = LIST_AREA_MIN_HEIGHT / LIST_AREA_HEIGHT;
+            self.list_split =
+                (self.list_split + response.drag_delta().y / LIST_AREA_HEIGHT).clamp(min, 1.0 - min);
+        }
+        if response.drag_released() {
+            self.save_data();
+        }
+    }
+
+    /// 检测刚结束或越过进度提醒点的任务并发出提醒，与界面布局无关，每帧调用
+    fn tick_tasks(&mut self) {
+        let mut just_finished_tasks = Vec::new();
+        let mut milestone_hits = Vec::new();
+        let mut halfway = false;
+        let mut lead_hits = Vec::new();
+
+        for task in &mut self.tasks {
+            if task.is_finished() && task.finished_at.is_none() {
+                task.finished_at = Some(Local::now());
+                task.frozen_display = None;
+                just_finished_tasks.push(task.clone());
+            }
+
+            let progress = task.progress();
+            for &pct in &self.milestones {
+                if progress * 100.0 >= pct as f32 && !task.fired_milestones.contains(&pct) {
+                    task.fired_milestones.push(pct);
+                    if !task.is_finished() {
+                        milestone_hits.push((task.name.clone(), pct));
+                    }
+                }
+            }
+
+            // 提前量不短于总时长时（例如推迟后的短任务）不提醒，免得一开始就响；
+            // 同一帧越过多个提前量时只提醒最近的一个
+            if !task.is_finished() {
+                let remaining = task.remaining();
+                let crossed: Vec<u64> = task
+                    .lead_reminders
+                    .iter()
+                    .copied()
+                    .filter(|&secs| {
+                        let lead = Duration::from_secs(secs);
+                        lead < task.duration && remaining <= lead && !task.lead_reminded.contains(&secs)
+                    })
+                    .collect();
+                if let Some(&nearest) = crossed.iter().min() {
+                    task.lead_reminded.extend(&crossed);
+                    task.lead_flash = Some(Instant::now());
+                    lead_hits.push((task.clone(), nearest));
+                }
+            }
+
+            if task.kind != TaskKind::Stopwatch
+                && !task.near_finish_seen
+                && !task.is_finished()
+                && task.remaining() <= NEAR_FINISH
+            {
+                task.near_finish_seen = true;
+                // 开始时就不足这么长的短任务不算
+                if self.scroll_on_near_finish && task.duration > NEAR_FINISH {
+                    self.scroll_to_task = Some(task.id);
+                }
+            }
+
+            if progress >= 0.5 && !task.halfway_chimed {
+                task.halfway_chimed = true;
+                halfway |= !task.is_finished();
+            }
+        }
+
+        if halfway && self.halfway_chime {
+            self.play_soft_chime();
+        }
+
+        for (task, secs) in lead_hits {
+            let body = format!("{} 还剩 {}", task.name, format_duration_words(Duration::from_secs(secs)));
+            if task.alert_notify {
+                Self::show_notification("即将结束", &body);
+            }
+            if task.alert_sound {
+                self.play_soft_chime();
+            }
+        }
+
+        for (name, pct) in milestone_hits {
+            let body = if pct == 50 {
+                format!("{} 已完成一半", name)
+            } else {
+                format!("{} 已完成 {}%", name, pct)
+            };
+            Self::show_notification("倒计时进度", &body);
+        }
+
+        for task in just_finished_tasks {
+            // 设为“步骤间只提示”的连续倒计时，中间步骤结束时只响切换音
+            let transition = task
+                .chain
+                .as_ref()
+                .is_some_and(|link| link.chain.quiet_transitions && link.next().is_some());
+            if transition {
+                if task.alert_sound {
+                    self.play_transition_beep();
+                }
+                self.history.push(task.clone());
+                self.trim_history();
+                self.acknowledge_task(task.id);
+                // 中间步骤已记入历史记录，卡片不再保留，免得间歇训练留下一串已结束的卡片
+                self.remove_tasks(&[task.id]);
+                self.save_data();
+                if let Some(next) = task.chain.as_ref().and_then(ChainLink::next) {
+                    self.start_chain_step(next);
+                }
+                continue;
+            }
+            if task.alert_sound && task.important {
+                self.play_important_alarm();
+            } else if task.alert_sound && let Some(link) = task.pomodoro {
+                self.play_pomodoro_chime(link.on_break);
+            } else if task.alert_sound {
+                self.play_alarm_pattern(task.alarm_pattern.unwrap_or(self.alarm_pattern));
+            }
+            if task.alert_notify {
+                Self::show_notification("倒计时结束", &task.finish_message());
+            }
+            self.run_finish_hooks(&task);
+            if !task.special && task.kind == TaskKind::Countdown {
+                self.last_finished = Some(task.clone());
+            }
+            self.history.push(task.clone());
+            self.trim_history();
+            self.save_data();
+            if !self.popup_pinned() {
+                self.show_finished_popup = Some(task.id);
+            }
+            self.check_focus_goal();
+            if let Some(next) = task.chain.as_ref().and_then(ChainLink::next) {
+                self.start_chain_step(next);
+            }
+            if let Some(next) = task.pomodoro.and_then(PomodoroLink::next) {
+                self.start_pomodoro_phase(next);
+            }
+            if let Some(timer) = task
+                .recurring
+                .and_then(|id| self.recurring.iter().find(|r| r.id == id))
+                .cloned()
+            {
+                self.start_recurring(&timer, Local::now());
+            }
+        }
+    }
+
+    /// 今天结束的任务（不含特别倒计时）的设定时长之和
+    fn focus_time_today(&self) -> Duration {
+        let today = Local::now().date_naive();
+        self.history
+            .iter()
+            .filter(|t| !t.special && t.finished_at.unwrap_or(t.created_at).date_naive() == today)
+            .map(|t| t.duration)
+            .sum()
+    }
+
+    /// 今天第一次达到专注目标时发送庆祝通知；日期变化后自然重新计算
+    fn check_focus_goal(&mut self) {
+        let today = Local::now().date_naive();
+        if self.focus_goal_minutes == 0 || self.focus_goal_reached_on == Some(today) {
+            return;
+        }
+        let done = self.focus_time_today();
+        if done >= Duration::from_secs(self.focus_goal_minutes * 60) {
+            self.focus_goal_reached_on = Some(today);
+            self.save_data();
+            Self::show_notification(
+                "🎉 今日专注目标达成",
+                &format!("今天已专注 {}", format_duration_words(done)),
+            );
+        }
+    }
+
+    /// 按当前设置重新建立同步连接，地址无效或端口被占用时在状态中提示
+    #[cfg(feature = "lan-sync")]
+    fn apply_sync(&mut self) {
+        self.sync_host = None;
+        self.sync_mirror = None;
+        self.sync_status = None;
+        let result = match self.sync_mode {
+            SyncMode::Off => return,
+            SyncMode::Host => sync::Host::new(self.sync_target.trim()).map(|host| self.sync_host = Some(host)),
+            SyncMode::Mirror => {
+                sync::Mirror::new(self.sync_bind.trim()).map(|mirror| self.sync_mirror = Some(mirror))
+            }
+        };
+        if let Err(e) = result {
+            self.sync_status = Some(format!("同步启动失败: {}", e));
+        }
+    }
+
+    /// 主机发送快照，镜像端接收快照并对新结束的任务提醒，每帧调用
+    #[cfg(feature = "lan-sync")]
+    fn tick_sync(&mut self) {
+        let title = self.window_title();
+        if let Some(host) = &mut self.sync_host {
+            let tasks = &self.tasks;
+            let result = host.send_if_due(&title, || {
+                tasks
+                    .iter()
+                    .filter(|t| t.kind != TaskKind::Stopwatch)
+                    .map(|t| sync::SyncTask {
+                        id: t.id,
+                        name: t.name.clone(),
+                        duration_ms: t.duration.as_millis() as u64,
+                        remaining_ms: t.remaining().as_millis() as u64,
+                        paused: t.paused,
+                    })
+                    .collect()
+            });
+            // 发送失败（例如网络暂时不可用）只提示，下一次间隔会自动重试
+            self.sync_status = result.err().map(|e| format!("发送失败: {}", e));
+        }
+
+        let finished = match &mut self.sync_mirror {
+            Some(mirror) => mirror.poll(),
+            None => return,
+        };
+        for task in finished {
+            self.play_alarm_sound();
+            Self::show_notification("主机的倒计时结束", &format!("任务“{}”已结束", task.name));
+        }
+    }
+
+    /// 镜像端的只读任务列表及与主机的连接状态
+    #[cfg(feature = "lan-sync")]
+    fn show_mirror_tasks(&self, ui: &mut egui::Ui) {
+        use egui::{ProgressBar, RichText};
+
+        let Some(mirror) = &self.sync_mirror else {
+            return;
+        };
+        ui.group(|ui| {
+            match (&mirror.host, mirror.since_last_message()) {
+                (Some(host), _) if mirror.connected() => {
+                    ui.label(RichText::new(format!("🔗 正在跟随主机“{}”（只读）", host)).strong());
+                }
+                (_, Some(since)) => {
+                    error_label(
+                        ui,
+                        self.palette(),
+                        format!("与主机断开连接，{} 前收到最后一次更新", format_duration_words(since)),
+                    );
+                }
+                (_, None) => {
+                    ui.label(format!("正在 {} 等待主机…", self.sync_bind.trim()));
+                }
+            }
+            for task in &mirror.tasks {
+                let remaining = mirror.remaining(task);
+                let state = if task.finished() {
+                    "（已结束）"
+                } else if task.paused {
+                    "（已暂停）"
+                } else {
+                    ""
+                };
+                ui.label(format!(
+                    "{}  {}{}",
+                    task.name,
+                    format_hms(remaining, self.compact_time),
+                    state
+                ));
+                let progress = if task.duration_ms == 0 {
+                    1.0
+                } else {
+                    1.0 - remaining.as_millis() as f32 / task.duration_ms as f32
+                };
+                ui.add(ProgressBar::new(progress));
+            }
+        });
+    }
+
+    /// 状态颜色的配色
+    fn palette(&self) -> &'static Palette {
+        if self.colorblind { &COLORBLIND_PALETTE } else { &STANDARD_PALETTE }
+    }
+
+    /// 顶部和全屏模式共用的时钟文字，其他时钟格式选项也应在这里组合
+    fn clock_text(&self) -> String {
+        let format = if self.clock_minutes_only { "%H:%M" } else { "%H:%M:%S" };
+        let now = match self.global_freeze {
+            Some(at) if self.freeze_clock => at,
+            _ => Local::now(),
+        };
+        now.format(format).to_string()
+    }
+
+    /// 进入或解除全局冻结。冻结借用各任务自身的暂停：只暂停原本在走的任务并记下编号，
+    /// 解除时只继续这些任务，用户自己暂停的任务保持暂停，时间也不会重复扣除
+    fn toggle_global_freeze(&mut self) {
+        if self.global_freeze.take().is_some() {
+            for task in &mut self.tasks {
+                if self.freeze_paused_ids.contains(&task.id) {
+                    task.resume();
+                }
+            }
+            self.freeze_paused_ids.clear();
+        } else {
+            self.global_freeze = Some(Local::now());
+            self.tick_global_freeze();
+        }
+    }
+
+    /// 暂停所有运行中且未锁定的任务
+    fn pause_all(&mut self) {
+        for task in &mut self.tasks {
+            if !task.locked && !task.is_finished() {
+                task.pause();
+            }
+        }
+    }
+
+    /// 继续所有已暂停且未锁定的任务，等待定时开始的除外
+    fn resume_all(&mut self) {
+        for task in &mut self.tasks {
+            if !task.locked && !task.is_finished() && task.scheduled_start.is_none() {
+                task.resume();
+            }
+        }
+    }
+
+    /// 停止所有未结束且未锁定的任务，效果与逐个点“停止”相同
+    fn stop_all(&mut self) {
+        let stopped: Vec<CountdownTask> = self
+            .tasks
+            .iter()
+            .filter(|t| !t.locked && !t.is_finished())
+            .cloned()
+            .collect();
+        let mut remove_ids = Vec::new();
+        for task in &stopped {
+            if task.kind == TaskKind::Stopwatch {
+                self.stop_stopwatch(task.id);
+            } else {
+                remove_ids.push(task.id);
+            }
+        }
+        self.delete_tasks(&remove_ids);
+        for task in &stopped {
+            if let Some(timer) = task.recurring.and_then(|id| self.recurring.iter().find(|r| r.id == id)).cloned() {
+                let after = match task.kind {
+                    TaskKind::Alarm { at } => at,
+                    _ => Local::now(),
+                };
+                self.start_recurring(&timer, after);
+            }
+        }
+    }
+
+    /// 冻结期间每帧调用：新添加或被其他功能继续的任务也立即暂停
+    fn tick_global_freeze(&mut self) {
+        if self.global_freeze.is_none() {
+            return;
+        }
+        for task in &mut self.tasks {
+            if !task.paused && !task.is_finished() {
+                task.pause();
+                // 闹钟按墙钟计时，不会暂停；不检查的话每帧都会重复记下
+                if task.paused {
+                    self.freeze_paused_ids.push(task.id);
+                }
+            }
+        }
+    }
+
+    /// 时钟下方的日期，例如“2026-10-16 星期五”
+    fn date_text(&self) -> String {
+        const WEEKDAYS: [&str; 7] = ["星期一", "星期二", "星期三", "星期四", "星期五", "星期六", "星期日"];
+        let now = Local::now();
+        let weekday = WEEKDAYS[now.weekday().num_days_from_monday() as usize];
+        format!("{} {}", now.format("%Y-%m-%d"), weekday)
+    }
+
+    /// 下一次重绘的间隔：只显示到分钟且没有需要逐秒刷新的内容时，等到下一个整分
+    fn repaint_interval(&self) -> Duration {
+        let busy = self.tasks.iter().any(|t| (!t.is_finished() && !t.paused) || t.counting_overtime())
+            || !self.active_sinks.is_empty()
+            || self.show_finished_popup.is_some()
+            || self.background_fit_toast.is_some()
+            || self.undo_toast.is_some()
+            || self.added_highlight.is_some()
+            || self.chess.as_ref().is_some_and(|c| c.active().is_some())
+            || self.tasks.iter().any(|t| t.auto_remove && t.finished_at.is_some())
+            || self.tasks.iter().any(|t| t.rung_at.is_some() && !t.acknowledged)
+            || self.tasks.iter().any(|t| t.scheduled_start.is_some())
+            || !self.status_image_path.trim().is_empty();
+        #[cfg(feature = "background-url")]
+        let busy = busy || self.background_download.is_some();
+        #[cfg(feature = "idle-detection")]
+        let busy = busy || (self.idle_pause && self.idle_supported);
+        #[cfg(feature = "lan-sync")]
+        let busy = busy || self.sync_mode != SyncMode::Off;
+        if !self.clock_minutes_only || busy {
+            return Duration::from_millis(200);
+        }
+        let now = Local::now();
+        let into_minute = Duration::from_secs(now.second() as u64) + Duration::from_nanos(now.nanosecond() as u64);
+        // 多等 50ms，确保醒来时已经跨过整分
+        Duration::from_secs(60).saturating_sub(into_minute) + Duration::from_millis(50)
+    }
+
+    /// 根据设置组装的结束动作，新的后端在这里加入
+    fn finish_hooks(&self) -> Vec<Box<dyn hook::FinishHook>> {
+        let mut hooks: Vec<Box<dyn hook::FinishHook>> = Vec::new();
+        if !self.finish_hook_enabled {
+            return hooks;
+        }
+        let command = self.finish_hook_command.trim();
+        if !command.is_empty() {
+            hooks.push(Box::new(hook::CommandHook {
+                command: command.to_string(),
+            }));
+        }
+        hooks
+    }
+
+    fn run_finish_hooks(&mut self, task: &CountdownTask) {
+        let hooks = self.finish_hooks();
+        if hooks.is_empty() {
+            return;
+        }
+        let Ok(json) = serde_json::to_string(task) else {
+            return;
+        };
+        for hook in hooks {
+            if let Err(e) = hook.run(&json) {
+                self.finish_hook_status = Some(format!("结束动作启动失败: {}", e));
+            }
+        }
+    }
+
+    /// 叠加层和状态图片中每个进行中计时的一行文字
+    fn active_timer_lines(&self) -> Vec<String> {
+        self.tasks
+            .iter()
+            .filter(|t| !t.is_finished())
+            .map(|t| {
+                let paused = if t.paused { "（暂停）" } else { "" };
+                format!("{}  {}{}", t.name, format_days_hms(t.shown_time(), self.compact_time), paused)
+            })
+            .collect()
+    }
+
+    /// 叠加层模式的全部界面：拖动移动窗口，右键退出
+    fn show_overlay(&mut self, ui: &mut egui::Ui) {
+        use egui::{RichText, Sense};
+
+        for line in self.active_timer_lines() {
+            ui.label(RichText::new(line).size(28.0).color(self.text_color));
+        }
+        let response = ui.interact(ui.max_rect(), ui.id().with("overlay_drag"), Sense::click_and_drag());
+        if response.drag_started() {
+            self.overlay_drag_requested = true;
+        }
+        response.context_menu(|ui| {
+            if ui.button("退出叠加层模式").clicked() {
+                self.overlay_mode = false;
+                self.save_data();
+                ui.close_menu();
+            }
+        });
+    }
+
+    /// 按设定的间隔把进行中的计时写成 PNG
+    fn tick_status_image(&mut self) {
+        let path = self.status_image_path.trim();
+        if path.is_empty() {
+            return;
+        }
+        let interval = Duration::from_secs(self.status_image_interval_secs.max(1));
+        if self.last_status_image.is_some_and(|t| t.elapsed() < interval) {
+            return;
+        }
+        self.last_status_image = Some(Instant::now());
+        let result = status_image::render(
+            CUSTOM_FONT_DATA,
+            &self.active_timer_lines(),
+            32.0,
+            self.text_color.to_srgba_unmultiplied(),
+            self.overlay_background.to_srgba_unmultiplied(),
+        )
+        .ok_or_else(|| "无法加载字体".to_string())
+        .and_then(|image| status_image::save(&image, path));
+        self.status_image_status = result.err().map(|e| format!("写入状态图片失败: {}", e));
+    }
+
+    /// 把最快结束的进行中任务写入文本文件；内容变化时才写，没有进行中的任务时删除文件。
+    /// 关闭功能或改了路径后删除之前写的文件，不留下过期的内容
+    fn tick_status_text(&mut self) {
+        let path = self.status_text_path.trim().to_string();
+        let enabled = self.status_text_enabled && !path.is_empty();
+        if let Some(old) = self.status_text_written.take_if(|old| !enabled || *old != path) {
+            if let Err(e) = fs::remove_file(&old)
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                self.status_text_status = Some(format!("删除旧的状态文件失败: {}", e));
+            }
+            self.last_status_text = None;
+        }
+        if !enabled {
+            return;
+        }
+        let path = path.as_str();
+        let text = self
+            .tasks
+            .iter()
+            .filter(|t| t.kind != TaskKind::Stopwatch && !t.is_finished())
+            .min_by_key(|t| t.remaining())
+            .map(|t| format!("{} {}\n", t.name, format_hms(t.remaining(), self.compact_time)));
+        if !self.status_text_dirty && text == self.last_status_text {
+            return;
+        }
+        self.status_text_dirty = false;
+        let result = match &text {
+            Some(text) => {
+                // 先写临时文件再改名，读取方不会读到写了一半的内容
+                let tmp = format!("{}.tmp", path);
+                fs::write(&tmp, text).and_then(|()| fs::rename(&tmp, path))
+            }
+            None => match fs::remove_file(path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                other => other,
+            },
+        };
+        self.status_text_status = result.err().map(|e| format!("写入状态文件失败: {}", e));
+        self.status_text_written = text.as_ref().map(|_| path.to_string());
+        self.last_status_text = text;
+    }
+
+    /// 全屏时钟布局：大号时钟和进行中任务的剩余时间，适合挂墙显示
+    fn show_fullscreen_clock(&self, ui: &mut egui::Ui) {
+        use egui::RichText;
+
+        let clock_size = (ui.available_height() * 0.25).min(ui.available_width() * 0.15);
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() * 0.1);
+            ui.label(
+                RichText::new(self.clock_text())
+                    .size(clock_size)
+                    .color(self.text_color),
+            );
+            if self.show_date {
+                ui.label(RichText::new(self.date_text()).size(clock_size * 0.25));
+            }
+            ui.add_space(20.0);
+            for task in self.tasks.iter().filter(|t| !t.is_finished()) {
+                let paused = if task.paused { "（已暂停）" } else { "" };
+                ui.label(
+                    RichText::new(format!(
+                        "{}  {}{}",
+                        task.name,
+                        format_days_hms(task.shown_time(), self.compact_time),
+                        paused
+                    ))
+                    .size(clock_size * 0.3),
+                );
+            }
+            ui.add_space(20.0);
+            ui.label(RichText::new("按 F11 或 Esc 退出全屏").small().weak());
+        });
+    }
+
+    /// 进行中任务剩余时间的总和与最大值
+    fn remaining_totals(&self) -> (Duration, Duration) {
+        self.tasks
+            .iter()
+            .filter(|t| t.kind != TaskKind::Stopwatch && !t.is_finished())
+            .map(|t| t.remaining())
+            .fold((Duration::ZERO, Duration::ZERO), |(sum, max), r| (sum + r, max.max(r)))
+    }
+
+    /// 已结束但尚未确认的任务数，用于历史记录标题旁的角标
+    fn unacknowledged_count(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|t| t.finished_at.is_some() && !t.acknowledged)
+            .count()
+    }
+
+    fn acknowledge_task(&mut self, id: usize) {
+        let mut overtime = None;
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+            task.acknowledged = true;
+            if task.counting_overtime() {
+                task.overtime = Some(task.overtime_now());
+                overtime = task.overtime;
+            }
+        }
+        if overtime.is_some()
+            && let Some(entry) = self.history.iter_mut().find(|t| t.id == id)
+        {
+            entry.overtime = overtime;
+            self.save_data();
+        }
+        // 历史记录里的副本也要标记，否则重启后会再次提醒
+        if let Some(entry) = self.history.iter_mut().find(|t| t.id == id && !t.acknowledged) {
+            entry.acknowledged = true;
+            self.save_data();
+        }
+    }
+
+    /// 最近 `days` 天（含今天）每天完成的任务数，按日期升序
+    fn daily_completion_counts(&self, days: i64) -> Vec<(NaiveDate, usize)> {
+        let today = Local::now().date_naive();
+        (0..days)
+            .rev()
+            .map(|offset| {
+                let date = today - chrono::Duration::days(offset);
+                let count = self
+                    .history
+                    .iter()
+                    .filter(|t| t.created_at.date_naive() == date)
+                    .count();
+                (date, count)
+            })
+            .collect()
+    }
+
+    /// 某天完成的任务（不含特别倒计时），按开始时间排序
+    fn history_on_day(&self, date: NaiveDate) -> Vec<&CountdownTask> {
+        let mut tasks: Vec<&CountdownTask> = self
+            .history
+            .iter()
+            .filter(|t| !t.special && t.created_at.date_naive() == date)
+            .collect();
+        tasks.sort_by_key(|t| t.created_at);
+        tasks
+    }
+
+    /// 以相同的任务名、时长和个性化设置重新开始某天完成的全部任务
+    fn repeat_day(&mut self, date: NaiveDate) {
+        let templates: Vec<CountdownTask> = self.history_on_day(date).into_iter().cloned().collect();
+        for template in templates {
+            self.restart_task(&template, TaskSource::Repeat);
+        }
+    }
+
+    /// 过去一年（含今天共 365 天）的每日完成数，返回第一天、今天和有完成的日子的数量
+    fn yearly_completion_counts(&self) -> (NaiveDate, NaiveDate, HashMap<NaiveDate, usize>) {
+        let today = Local::now().date_naive();
+        let first = today - chrono::Duration::days(364);
+        let mut counts: HashMap<NaiveDate, usize> = HashMap::new();
+        for task in &self.history {
+            let date = task.created_at.date_naive();
+            if date >= first && date <= today {
+                *counts.entry(date).or_default() += 1;
+            }
+        }
+        (first, today, counts)
+    }
+
+    /// 热力图的起始日：第一天所在周的周一，保证每列都是完整的一周
+    fn heatmap_start(first: NaiveDate) -> NaiveDate {
+        first - chrono::Duration::days(first.weekday().num_days_from_monday() as i64)
+    }
+
+    /// 热力图中数量对应的颜色档位（1 到 4），按占最大值的比例分档，数量很少的日子也能看出来
+    fn heat_level(count: usize, max: usize) -> usize {
+        (count * 4).div_ceil(max).clamp(1, 4)
+    }
+
+    /// 过去一年的每日完成数热力图：每列一周（周一在上），颜色深浅表示数量
+    fn show_activity_heatmap(&self, ui: &mut egui::Ui) {
+        use egui::{pos2, vec2, Align2, FontId, Rect, Sense};
+
+        let (first, today, counts) = self.yearly_completion_counts();
+        let start = Self::heatmap_start(first);
+        let max = counts.values().copied().max().unwrap_or(0);
+        if max == 0 {
+            ui.label("过去一年还没有完成的任务");
+            return;
+        }
+
+        let weeks = ((today - start).num_days() / 7 + 1) as usize;
+        let label_height = 14.0;
+        let cell = ((ui.available_width() - 4.0) / weeks as f32).clamp(4.0, 14.0);
+        let gap = (cell * 0.15).max(1.0);
+        let (rect, _) = ui.allocate_exact_size(
+            vec2(cell * weeks as f32, label_height + cell * 7.0),
+            Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+
+        let empty = ui.visuals().faint_bg_color;
+        let mut last_month = None;
+        for week in 0..weeks {
+            let left = rect.left() + week as f32 * cell;
+            let week_start = start + chrono::Duration::days(week as i64 * 7);
+            // 某月第一次出现的那一列上方标注月份
+            if last_month != Some(week_start.month()) {
+                last_month = Some(week_start.month());
+                painter.text(
+                    pos2(left, rect.top()),
+                    Align2::LEFT_TOP,
+                    format!("{}月", week_start.month()),
+                    FontId::proportional(10.0),
+                    self.text_color,
+                );
+            }
+            for day in 0..7 {
+                let date = week_start + chrono::Duration::days(day);
+                if date < first || date > today {
+                    continue;
+                }
+                let top = rect.top() + label_height + day as f32 * cell;
+                let cell_rect =
+                    Rect::from_min_size(pos2(left, top), vec2(cell - gap, cell - gap));
+                let count = counts.get(&date).copied().unwrap_or(0);
+                let color = if count == 0 {
+                    empty
+                } else {
+                    self.palette().heat[Self::heat_level(count, max) - 1]
+                };
+                painter.rect_filled(cell_rect, 2.0, color);
+                ui.interact(cell_rect, ui.id().with(("heatmap_cell", date)), Sense::hover())
+                    .on_hover_text(format!("{}：完成 {} 个", date.format("%Y-%m-%d"), count));
+            }
+        }
+    }
+
+    /// 把统计页的每日完成数柱状图和过去一年的热力图上下排成一张 SVG，布局与界面中的图表一致；
+    /// 两者都没有数据时返回 `None`
+    fn stats_svg(&self) -> Option<String> {
+        let sections: Vec<(String, f32)> = [self.completion_chart_svg(), self.activity_heatmap_svg()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if sections.is_empty() {
+            return None;
+        }
+        let height: f32 = sections.iter().map(|(_, h)| h).sum();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n",
+            w = STATS_SVG_WIDTH,
+            h = height,
+        );
+        let mut top = 0.0;
+        for (section, section_height) in sections {
+            svg.push_str(&format!("<g transform=\"translate(0,{})\">\n{}</g>\n", top, section));
+            top += section_height;
+        }
+        svg.push_str("</svg>\n");
+        Some(svg)
+    }
+
+    /// 每日完成数柱状图的 SVG 元素和高度；没有数据时返回 `None`
+    fn completion_chart_svg(&self) -> Option<(String, f32)> {
+        let counts = self.daily_completion_counts(self.stats_days);
+        let max = counts.iter().map(|(_, c)| *c).max().filter(|&m| m > 0)?;
+
+        let (width, height, label_height, title_height) = (STATS_SVG_WIDTH, 240.0_f32, 18.0, 28.0);
+        let chart_top = title_height;
+        let chart_bottom = height - label_height;
+        let slot_width = width / counts.len() as f32;
+        let label_every = (counts.len() / 7).max(1);
+        let text_color = STATS_SVG_TEXT;
+
+        let mut svg = format!(
+            "<text x=\"8\" y=\"20\" font-size=\"14\" fill=\"{c}\">近{d}天每日完成数</text>\n\
+             <line x1=\"0\" y1=\"{y}\" x2=\"{w}\" y2=\"{y}\" stroke=\"{c}\"/>\n",
+            w = width,
+            c = text_color,
+            d = self.stats_days,
+            y = chart_bottom,
+        );
+        for (i, (date, count)) in counts.iter().enumerate() {
+            let left = i as f32 * slot_width;
+            let bar_height = *count as f32 / max as f32 * (chart_bottom - chart_top - label_height);
+            let bar_top = chart_bottom - bar_height;
+            svg.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"2\" fill=\"#468cdc\"><title>{}：完成 {} 个</title></rect>\n",
+                left + slot_width * 0.15,
+                bar_top,
+                slot_width * 0.7,
+                bar_height,
+                date.format("%Y-%m-%d"),
+                count
+            ));
+            if *count > 0 {
+                svg.push_str(&format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"11\" text-anchor=\"middle\" fill=\"{}\">{}</text>\n",
+                    left + slot_width / 2.0,
+                    bar_top - 2.0,
+                    text_color,
+                    count
+                ));
+            }
+            if i % label_every == 0 || i == counts.len() - 1 {
+                svg.push_str(&format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\" text-anchor=\"middle\" fill=\"{}\">{}</text>\n",
+                    left + slot_width / 2.0,
+                    height - 4.0,
+                    text_color,
+                    date.format("%m-%d")
+                ));
+            }
+        }
+        Some((svg, height))
+    }
+
+    /// 过去一年热力图的 SVG 元素和高度，颜色与界面中的热力图相同；没有数据时返回 `None`
+    fn activity_heatmap_svg(&self) -> Option<(String, f32)> {
+        let (first, today, counts) = self.yearly_completion_counts();
+        let max = counts.values().copied().max().filter(|&m| m > 0)?;
+        let start = Self::heatmap_start(first);
+        let weeks = ((today - start).num_days() / 7 + 1) as usize;
+        let (title_height, label_height, margin) = (28.0_f32, 14.0_f32, 8.0_f32);
+        let cell = ((STATS_SVG_WIDTH - margin * 2.0) / weeks as f32).min(14.0);
+        let gap = (cell * 0.15).max(1.0);
+        let cells_top = title_height + label_height;
+
+        let mut svg = format!(
+            "<text x=\"8\" y=\"20\" font-size=\"14\" fill=\"{}\">过去一年每日完成数</text>\n",
+            STATS_SVG_TEXT
+        );
+        let mut last_month = None;
+        for week in 0..weeks {
+            let left = margin + week as f32 * cell;
+            let week_start = start + chrono::Duration::days(week as i64 * 7);
+            if last_month != Some(week_start.month()) {
+                last_month = Some(week_start.month());
+                svg.push_str(&format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\" fill=\"{}\">{}月</text>\n",
+                    left,
+                    cells_top - 3.0,
+                    STATS_SVG_TEXT,
+                    week_start.month()
+                ));
+            }
+            for day in 0..7 {
+                let date = week_start + chrono::Duration::days(day);
+                if date < first || date > today {
+                    continue;
+                }
+                let count = counts.get(&date).copied().unwrap_or(0);
+                let fill = if count == 0 {
+                    "#ebedf0".to_string()
+                } else {
+                    let [r, g, b, _] = self.palette().heat[Self::heat_level(count, max) - 1].to_array();
+                    format!("#{:02x}{:02x}{:02x}", r, g, b)
+                };
+                svg.push_str(&format!(
+                    "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"2\" fill=\"{}\"><title>{}：完成 {} 个</title></rect>\n",
+                    left,
+                    cells_top + day as f32 * cell,
+                    cell - gap,
+                    cell - gap,
+                    fill,
+                    date.format("%Y-%m-%d"),
+                    count
+                ));
+            }
+        }
+        Some((svg, cells_top + cell * 7.0 + margin))
+    }
+
+    fn export_stats_image(&mut self) {
+        let path = match self.stats_export_path.trim() {
+            "" => "stats.svg".to_string(),
+            path => path.to_string(),
+        };
+        let Some(svg) = self.stats_svg() else {
+            self.stats_export_status = Some("还没有完成的任务，没有可导出的图表".to_string());
+            return;
+        };
+        self.stats_export_status = Some(match fs::write(&path, svg) {
+            Ok(()) => format!("已导出到 {}", path),
+            Err(e) => format!("导出失败: {}", e),
+        });
+    }
+
+    fn show_completion_chart(&self, ui: &mut egui::Ui) {
+        use egui::{pos2, vec2, Align2, FontId, Rect, Sense, Stroke};
+
+        let counts = self.daily_completion_counts(self.stats_days);
+        let max = counts.iter().map(|(_, c)| *c).max().unwrap_or(0);
+        if max == 0 {
+            ui.label("这段时间内还没有完成的任务");
+            return;
+        }
+
+        let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), 140.0), Sense::hover());
+        let painter = ui.painter_at(rect);
+        let label_height = 14.0;
+        let chart_bottom = rect.bottom() - label_height;
+        let slot_width = rect.width() / counts.len() as f32;
+        // 天数较多时只每隔几天标注一次日期，避免文字重叠
+        let label_every = (counts.len() / 7).max(1);
+
+        painter.line_segment(
+            [pos2(rect.left(), chart_bottom), pos2(rect.right(), chart_bottom)],
+            Stroke::new(1.0, self.text_color),
+        );
+        for (i, (date, count)) in counts.iter().enumerate() {
+            let left = rect.left() + i as f32 * slot_width;
+            let slot = Rect::from_min_max(pos2(left, rect.top()), pos2(left + slot_width, chart_bottom));
+            let height = *count as f32 / max as f32 * (slot.height() - label_height);
+            let bar = Rect::from_min_max(
+                pos2(slot.left() + slot_width * 0.15, chart_bottom - height),
+                pos2(slot.right() - slot_width * 0.15, chart_bottom),
+            );
+
+            let response = ui
+                .interact(slot, ui.id().with(("completion_bar", i)), Sense::hover())
+                .on_hover_text(format!("{}：完成 {} 个", date.format("%Y-%m-%d"), count));
+            let color = if response.hovered() {
+                Color32::from_rgb(120, 190, 255)
+            } else {
+                Color32::from_rgb(70, 140, 220)
+            };
+            painter.rect_filled(bar, 2.0, color);
+
+            if *count > 0 {
+                painter.text(
+                    pos2(bar.center().x, bar.top() - 2.0),
+                    Align2::CENTER_BOTTOM,
+                    count.to_string(),
+                    FontId::proportional(11.0),
+                    self.text_color,
+                );
+            }
+            if i % label_every == 0 || i == counts.len() - 1 {
+                painter.text(
+                    pos2(slot.center().x, rect.bottom()),
+                    Align2::CENTER_BOTTOM,
+                    date.format("%m-%d").to_string(),
+                    FontId::proportional(10.0),
+                    self.text_color,
+                );
+            }
+        }
+    }
+
+    fn show_overrun_badge(ui: &mut egui::Ui, task: &CountdownTask, compact: bool, palette: &Palette) {
+        let Some(extra) = task.overrun() else {
+            return;
+        };
+        let secs = extra.as_secs();
+        let text = if secs >= 3600 {
+            format!("用时 +{}时{}分 含暂停", secs / 3600, (secs / 60) % 60)
+        } else if secs >= 60 {
+            format!("用时 +{}分 含暂停", secs / 60)
+        } else {
+            format!("用时 +{}秒 含暂停", secs)
+        };
+        ui.label(
+            egui::RichText::new(text)
+                .small()
+                .color(Color32::BLACK)
+                .background_color(palette.warning),
+        )
+        .on_hover_text(format!(
+            "实际用时 {}，设定时长 {}",
+            format_hms(task.duration + extra, compact),
+            format_hms(task.duration, compact)
+        ));
+    }
+
+    /// 清理已播放完的提醒音，并停止超过最长时间仍在响的提醒
+    fn cleanup_alarms(&mut self) {
+        let max = (self.alarm_max_secs > 0).then(|| Duration::from_secs(self.alarm_max_secs));
+        self.active_sinks.retain(|alarm| {
+            if max.is_some_and(|max| alarm.started.elapsed() >= max) {
+                alarm.sink.stop();
+                return false;
+            }
+            !alarm.sink.empty()
+        });
+    }
+
+    /// 让背景音跟随任务状态：进行中播放、暂停时暂停，结束或被移除后停止
+    fn tick_ambient(&mut self) {
+        let running: HashMap<usize, bool> = self
+            .tasks
+            .iter()
+            .filter(|t| !t.is_finished() && !t.ambient.is_off())
+            .map(|t| (t.id, t.paused))
+            .collect();
+        self.ambient_sinks.retain(|id, sink| {
+            let keep = running.contains_key(id);
+            if !keep {
+                sink.stop();
+            }
+            keep
+        });
+        self.ambient_failed.retain(|id| running.contains_key(id));
+
+        for task in &self.tasks {
+            let Some(&paused) = running.get(&task.id) else {
+                continue;
+            };
+            if let Some(sink) = self.ambient_sinks.get(&task.id) {
+                if paused && !sink.is_paused() {
+                    sink.pause();
+                } else if !paused && sink.is_paused() {
+                    sink.play();
+                }
+                continue;
+            }
+            if self.ambient_failed.contains(&task.id) {
+                continue;
+            }
+            let sink = ambient::open(&task.ambient).and_then(|source| {
+                let sink = Sink::try_new(&self.stream_handle).map_err(|e| e.to_string())?;
+                sink.set_volume(self.effective_volume());
+                sink.append(source);
+                if paused {
+                    sink.pause();
+                }
+                Ok(sink)
+            });
+            match sink {
+                Ok(sink) => {
+                    self.ambient_sinks.insert(task.id, sink);
+                }
+                Err(e) => {
+                    self.ambient_failed.insert(task.id);
+                    self.ambient_status = Some(format!("“{}”的背景音: {}", task.name, e));
+                }
+            }
+        }
+    }
+
+    /// 登记新播放的声音，超过上限时先停掉最早的
+    fn track_sink(&mut self, sink: Sink) {
+        while self.active_sinks.len() >= MAX_ACTIVE_SINKS {
+            self.active_sinks.remove(0).sink.stop();
+        }
+        self.active_sinks.push(ActiveAlarm {
+            sink,
+            started: Instant::now(),
+        });
+    }
+
+    fn stop_all_sounds(&mut self) {
+        for alarm in self.active_sinks.drain(..) {
+            alarm.sink.stop();
+        }
+    }
+
+    /// 提醒音无法解码时的最后手段：合成三声短促的正弦波蜂鸣
+    fn append_fallback_beep(sink: &Sink) {
+        for _ in 0..3 {
+            sink.append(
+                SineWave::new(880.0)
+                    .take_duration(Duration::from_millis(250))
+                    .amplify(0.3),
+            );
+            sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(150)));
+        }
+    }
+
+    fn effective_volume(&self) -> f32 {
+        if self.muted { 0.0 } else { self.alarm_volume }
+    }
+
+    /// 音量或静音改变后，同步到正在播放的提醒并保存
+    fn apply_volume(&mut self) {
+        let volume = self.effective_volume();
+        for alarm in &self.active_sinks {
+            alarm.sink.set_volume(volume);
+        }
+        for sink in self.ambient_sinks.values() {
+            sink.set_volume(volume);
+        }
+        self.save_data();
+    }
+
+    /// 按提醒音样式把每一次响铃和间隔依次排进同一个 Sink，
+    /// 音量、静音和最长时间对整段样式同时生效
+    fn play_alarm_sound(&mut self) {
+        self.play_alarm_pattern(self.alarm_pattern);
+    }
+
+    /// 按指定的提醒音样式播放，用于带有自己样式的任务
+    fn play_alarm_pattern(&mut self, pattern: AlarmPattern) {
+        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
+            sink.set_volume(self.effective_volume());
+            self.append_alarm_pattern(&sink, pattern, 1.0);
+            self.track_sink(sink);
+        }
+    }
+
+    /// 预设开始时的提示音：两声短促的上行音
+    fn play_start_sound(&mut self) {
+        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
+            sink.set_volume(self.effective_volume());
+            for freq in [660.0, 990.0] {
+                sink.append(
+                    SineWave::new(freq)
+                        .take_duration(Duration::from_millis(100))
+                        .amplify(0.3),
+                );
+                sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(60)));
+            }
+            self.track_sink(sink);
+        }
+    }
+
+    /// 重要任务的结束提醒：先响两串急促的升调短音，再以更大的音量播放提醒音
+    fn play_important_alarm(&mut self) {
+        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
+            sink.set_volume(self.effective_volume());
+            for _ in 0..2 {
+                for freq in [988.0, 1319.0, 1760.0] {
+                    sink.append(
+                        SineWave::new(freq)
+                            .take_duration(Duration::from_millis(120))
+                            .amplify(0.5),
+                    );
+                }
+                sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(200)));
+            }
+            self.append_alarm_pattern(&sink, self.alarm_pattern, IMPORTANT_ALARM_GAIN);
+            self.track_sink(sink);
+        }
+    }
+
+    /// 按提醒音设置（次数、间隔、渐强）把提醒音依次加入 sink。
+    /// 数据文件可能被手动改过，负数、NaN 或过大的秒数按 0 处理，次数限制在界面允许的范围内
+    fn append_alarm_pattern(&self, sink: &Sink, pattern: AlarmPattern, gain: f32) {
+        let interval = Duration::try_from_secs_f32(pattern.interval_secs).unwrap_or_default();
+        let fade_in = Duration::try_from_secs_f32(pattern.fade_in_secs).unwrap_or_default();
+        for i in 0..pattern.repeat.clamp(1, 10) {
+            if i > 0 && !interval.is_zero() {
+                sink.append(Zero::<f32>::new(1, 48000).take_duration(interval));
+            }
+            let cursor = Cursor::new(ALARM_WAV);
+            match Decoder::new(cursor) {
+                Ok(source) => sink.append(source.fade_in(fade_in).amplify(gain)),
+                Err(_) => Self::append_fallback_beep(sink),
+            }
+        }
+    }
+
+    /// 番茄钟阶段切换的合成音：专注结束为下行三音，休息结束为上行三音，听声音就能分辨
+    fn play_pomodoro_chime(&mut self, break_ended: bool) {
+        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
+            sink.set_volume(self.effective_volume());
+            let mut notes = [523.0, 659.0, 784.0];
+            if !break_ended {
+                notes.reverse();
+            }
+            for freq in notes {
+                sink.append(
+                    SineWave::new(freq)
+                        .take_duration(Duration::from_millis(220))
+                        .amplify(0.35),
+                );
+                sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(60)));
+            }
+            self.track_sink(sink);
+        }
+    }
+
+    /// 过半和提前提醒用的两声短而轻的合成音，和结束提醒一样受音量和静音控制
+    fn play_soft_chime(&mut self) {
+        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
+            sink.set_volume(self.effective_volume());
+            for freq in [660.0, 880.0] {
+                sink.append(
+                    SineWave::new(freq)
+                        .take_duration(Duration::from_millis(180))
+                        .amplify(0.15),
+                );
+                sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(60)));
+            }
+            self.track_sink(sink);
+        }
+    }
+
+    /// 连续倒计时步骤切换音：两短一长，类似间歇训练计时器
+    fn play_transition_beep(&mut self) {
+        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
+            sink.set_volume(self.effective_volume());
+            for (freq, millis) in [(880.0, 120), (880.0, 120), (1320.0, 400)] {
+                sink.append(
+                    SineWave::new(freq)
+                        .take_duration(Duration::from_millis(millis))
+                        .amplify(0.3),
+                );
+                sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(100)));
+            }
+            self.track_sink(sink);
+        }
+    }
+
+    /// 报时音：整点按钟点数敲响（12 小时制），半点只敲一下
+    fn play_hour_chime(&mut self, strikes: u32) {
+        if let Ok(sink) = Sink::try_new(&self.stream_handle) {
+            sink.set_volume(self.effective_volume());
+            for _ in 0..strikes {
+                sink.append(
+                    SineWave::new(523.0)
+                        .take_duration(Duration::from_millis(400))
+                        .amplify(0.15),
+                );
+                sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(350)));
+            }
+            self.track_sink(sink);
+        }
+    }
+
+    /// 每帧检查是否到了整点或半点，每个时刻只报一次
+    fn tick_hour_chime(&mut self) {
+        if !self.hour_chime || self.muted {
+            return;
+        }
+        let now = Local::now();
+        let mark = (now.date_naive(), now.hour(), now.minute());
+        let strikes = match now.minute() {
+            0 => match now.hour() % 12 {
+                0 => 12,
+                h => h,
+            },
+            30 if self.half_hour_chime => 1,
+            _ => return,
+        };
+        if self.last_hour_chime == Some(mark) {
+            return;
+        }
+        self.last_hour_chime = Some(mark);
+        self.play_hour_chime(strikes);
+    }
+
+    fn show_notification(summary: &str, body: &str) {
+        let _ = notify_rust::Notification::new()
+            .summary(summary)
+            .body(body)
+            .show();
+    }
+
+    fn background_texture_from(ctx: &egui::Context, img: &image::DynamicImage) -> egui::TextureHandle {
+        let size = [img.width() as usize, img.height() as usize];
+        let img = img.to_rgba8();
+        let pixels = img.as_flat_samples();
+        let color_image = egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
+        ctx.load_texture("background", color_image, TextureOptions::LINEAR)
+    }
+
+    /// 从背景文件夹选出本次运行使用的图片，选中后整个运行期间保持不变
+    fn pick_background_from_dir(&mut self) -> Option<PathBuf> {
+        let dir = self.background_dir.trim();
+        if dir.is_empty() {
+            return None;
+        }
+        if let Some(choice) = &self.background_dir_choice {
+            return Some(choice.clone());
+        }
+        if self.background_dir_status.is_some() {
+            // 已经失败过，等用户点“应用”后再重新扫描
+            return None;
+        }
+        let mut images: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| {
+                    p.extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| BACKGROUND_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                })
+                .collect(),
+            Err(e) => {
+                self.background_dir_status = Some(format!("无法读取文件夹: {}", e));
+                return None;
+            }
+        };
+        if images.is_empty() {
+            self.background_dir_status = Some("文件夹中没有图片".to_string());
+            return None;
+        }
+        images.sort();
+        let index = if self.background_random {
+            // 只需每次启动不同，用当前时间的纳秒部分即可，无需引入随机数依赖
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.subsec_nanos());
+            nanos as usize % images.len()
+        } else {
+            0
+        };
+        let choice = images.swap_remove(index);
+        self.background_dir_status = Some(format!("本次背景: {}", choice.display()));
+        self.background_dir_choice = Some(choice.clone());
+        Some(choice)
+    }
+
+    /// 当前是否使用深色配色，自动模式每次调用都按当前时间计算，跨过设定的整点即切换
+    fn dark_theme(&self) -> bool {
+        match self.theme_mode {
+            ThemeMode::Light => false,
+            ThemeMode::Dark => true,
+            ThemeMode::Auto => {
+                let hour = Local::now().hour();
+                let (day, night) = (self.theme_day_start_hour, self.theme_night_start_hour);
+                if day <= night {
+                    !(day..night).contains(&hour)
+                } else {
+                    // 白天跨过午夜的设置，例如 20 点到次日 8 点
+                    (night..day).contains(&hour)
+                }
+            }
+        }
+    }
+
+    /// 关闭背景后界面是纯色底色，为背景图选的文字颜色可能与之太接近而看不清，
+    /// 此时改用配色自带的文字颜色
+    fn readable_text_color(&self, visuals: &egui::Visuals) -> Color32 {
+        let luma = |c: Color32| 0.299 * c.r() as f32 + 0.587 * c.g() as f32 + 0.114 * c.b() as f32;
+        if !self.show_background && (luma(self.text_color) - luma(visuals.panel_fill)).abs() < 100.0 {
+            visuals.widgets.noninteractive.fg_stroke.color
+        } else {
+            self.text_color
+        }
+    }
+
+    fn load_background(&mut self, ctx: &egui::Context) {
+        if !self.show_background || self.background_texture.is_some() {
+            return;
+        }
+        if let Some(path) = self.pick_background_from_dir() {
+            match image::open(&path) {
+                Ok(img) => {
+                    self.background_texture = Some(Self::background_texture_from(ctx, &img));
+                    return;
+                }
+                Err(e) => {
+                    self.background_dir_status = Some(format!("无法打开 {}: {}", path.display(), e));
+                    self.background_dir_choice = None;
+                }
+            }
+        }
+        // 其次使用从 URL 缓存的背景，文件丢失时回退到默认背景
+        let path = self
+            .background_path
+            .as_deref()
+            .filter(|p| Path::new(p).exists())
+            .unwrap_or(BACKGROUND_IMAGE_PATH);
+        if Path::new(path).exists()
+            && let Ok(img) = image::open(path)
+        {
+            self.background_texture = Some(Self::background_texture_from(ctx, &img));
+        }
+    }
+
+    #[cfg(feature = "background-url")]
+    fn start_background_download(&mut self) {
+        let url = self.background_url_input.trim().to_string();
+        if url.is_empty() {
+            self.background_status = Some("请输入图片 URL".to_string());
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = ureq::get(&url)
+                .call()
+                .map_err(|e| format!("下载失败: {}", e))
+                .and_then(|resp| {
+                    let mut bytes = Vec::new();
+                    resp.into_reader()
+                        .take(BACKGROUND_DOWNLOAD_LIMIT)
+                        .read_to_end(&mut bytes)
+                        .map_err(|e| format!("读取失败: {}", e))?;
+                    Ok(bytes)
+                });
+            let _ = tx.send(result);
+        });
+        self.background_download = Some(rx);
+        self.background_status = Some("正在下载背景图片…".to_string());
+    }
+
+    #[cfg(feature = "background-url")]
+    fn poll_background_download(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.background_download else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err("下载线程意外退出".to_string()),
+        };
+        self.background_download = None;
+
+        let decoded = result.and_then(|bytes| {
+            image::load_from_memory(&bytes).map_err(|e| format!("图片解码失败: {}", e))
+        });
+        match decoded {
+            Ok(img) => {
+                self.background_texture = Some(Self::background_texture_from(ctx, &img));
+                if let Err(e) = img.to_rgba8().save(BACKGROUND_CACHE_PATH) {
+                    self.background_status = Some(format!("背景已更新，但缓存到本地失败: {}", e));
+                } else {
+                    self.background_path = Some(BACKGROUND_CACHE_PATH.to_string());
+                    self.save_data();
+                    self.background_status = Some("背景图片已更新".to_string());
+                }
+            }
+            Err(msg) => {
+                self.background_status = Some(format!("{}，已保留原背景", msg));
+            }
+        }
+    }
+}
+
+impl App for ClockApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut Frame) {
+        use egui::*;
+
+        if self.quit_requested {
+            frame.close();
+        } else if self.minimize_requested {
+            self.minimize_requested = false;
+            frame.set_minimized(true);
+        }
+
+        self.check_pinned_monitor(frame);
+
+        // F11 切换全屏时钟，全屏时 Esc 总能退出
+        let toggle_fullscreen = ctx.input_mut(|i| {
+            i.consume_key(Modifiers::NONE, Key::F11)
+                || (self.fullscreen && i.consume_key(Modifiers::NONE, Key::Escape))
+        });
+        if toggle_fullscreen {
+            self.fullscreen = !self.fullscreen;
+            frame.set_fullscreen(self.fullscreen);
+            self.save_data();
+        }
+        if self.pin_monitor_requested {
+            self.pin_monitor_requested = false;
+            self.pin_to_current_monitor(frame);
+        }
+
+        let title = self.window_title();
+        if title != self.applied_title {
+            frame.set_window_title(&title);
+            self.applied_title = title;
+        }
+
+        if self.applied_overlay != Some(self.overlay_mode) {
+            frame.set_decorations(!self.overlay_mode);
+            frame.set_always_on_top(self.overlay_mode);
+            self.applied_overlay = Some(self.overlay_mode);
+        }
+
+        self.cleanup_alarms();
+        self.tick_scheduled_starts();
+        self.tick_global_freeze();
+        self.tick_tasks();
+        self.tick_auto_remove();
+        self.tick_high_priority_alarms();
+        self.tick_chess();
+        self.tick_status_image();
+        self.tick_status_text();
+        self.tick_hour_chime();
+        self.tick_ambient();
+        if self.added_highlight.is_some_and(|(_, at)| at.elapsed() >= ADDED_HIGHLIGHT) {
+            self.added_highlight = None;
+        }
+        #[cfg(feature = "idle-detection")]
+        self.check_idle();
+        #[cfg(feature = "lan-sync")]
+        self.tick_sync();
+
+        let mut style = (*ctx.style()).clone();
+        style.visuals = if self.dark_theme() {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        style.visuals.override_text_color = Some(self.readable_text_color(&style.visuals));
+        ctx.set_style(style);
+
+        self.load_background(ctx);
+        #[cfg(feature = "background-url")]
+        self.poll_background_download(ctx);
+
+        if self.show_background
+            && let Some(texture) = &self.background_texture
+        {
+            let painter = ctx.layer_painter(LayerId::background());
+            let screen = ctx.input(|i| i.screen_rect());
+            let (rect, uv) = self.background_fit.layout(screen, texture.size_vec2());
+            painter.image(texture.id(), rect, uv, Color32::WHITE);
+        }
+
+        // 不在输入框中打字时：Ctrl+↑/↓ 调节音量，M 切换静音，B 切换背景铺放方式，Ctrl+Z 撤销删除
+        if !ctx.wants_keyboard_input() {
+            let (up, down, mute, fit, undo) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(Modifiers::COMMAND, Key::ArrowUp),
+                    i.consume_key(Modifiers::COMMAND, Key::ArrowDown),
+                    i.consume_key(Modifiers::NONE, Key::M),
+                    i.consume_key(Modifiers::NONE, Key::B),
+                    i.consume_key(Modifiers::COMMAND, Key::Z),
+                )
+            });
+            if undo {
+                self.undo_delete();
+            }
+            if fit {
+                self.background_fit = self.background_fit.next();
+                self.background_fit_toast = Some(Instant::now());
+                self.save_data();
+            }
+            if up || down {
+                let step = if up { 0.1 } else { -0.1 };
+                self.alarm_volume = (self.alarm_volume + step).clamp(0.0, 1.0);
+            }
+            if mute {
+                self.muted = !self.muted;
+            }
+            if up || down || mute {
+                self.apply_volume();
+            }
+        }
+
+        // 不在输入框中打字时，按 N 或 Ctrl+N 聚焦倒计时输入框
+        let focus_duration_input = !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| {
+                i.consume_key(Modifiers::NONE, Key::N) || i.consume_key(Modifiers::COMMAND, Key::N)
+            });
+
+        let panel_frame = if self.overlay_mode {
+            egui::Frame::none().fill(self.overlay_background).inner_margin(8.0)
+        } else {
+            egui::Frame::central_panel(&ctx.style())
+        };
+        if self.task_layout == TaskLayout::Side && !self.overlay_mode && !self.fullscreen {
+            let panel = SidePanel::right("task_side_panel")
+                .resizable(true)
+                .default_width(self.side_panel_width)
+                .width_range(240.0..=800.0)
+                .show(ctx, |ui| {
+                    ui.heading("进行中");
+                    let height = ui.available_height() - 40.0;
+                    self.show_task_list(ui, height);
+                });
+            // 拖动结束后记住侧栏宽度
+            let width = panel.response.rect.width();
+            if (width - self.side_panel_width).abs() >= 1.0 && !ctx.input(|i| i.pointer.any_down()) {
+                self.side_panel_width = width;
+                self.save_data();
+            }
+        }
+
+        CentralPanel::default().frame(panel_frame).show(ctx, |ui| {
+            if self.overlay_mode {
+                self.show_overlay(ui);
+                return;
+            }
+            if self.fullscreen {
+                self.show_fullscreen_clock(ui);
+                return;
+            }
+
+            ui.vertical_centered(|ui| {
+                ui.add_space(10.0);
+                ui.heading(
+                    RichText::new(self.clock_text())
+                        .size(48.0)
+                        .color(self.text_color),
+                );
+                if self.show_date {
+                    ui.label(RichText::new(self.date_text()).size(18.0));
+                }
+                if let Some(warning) = &self.config_warning {
+                    error_label(ui, self.palette(), warning);
+                }
+                ui.horizontal(|ui| {
+                    let frozen = self.global_freeze.is_some();
+                    let label = if frozen { "❄ 已冻结，点击恢复" } else { "❄ 冻结" };
+                    if ui
+                        .selectable_label(frozen, label)
+                        .on_hover_text("停止所有计时，恢复后从停下的地方继续；闹钟和特别倒计时按墙钟时间照常响铃")
+                        .clicked()
+                    {
+                        self.toggle_global_freeze();
+                    }
+                    ui.checkbox(&mut self.freeze_clock, "同时冻结时钟");
+                });
+                ui.add_space(10.0);
+            });
+
+            let (sum, max) = self.remaining_totals();
+            if sum > Duration::ZERO {
+                ui.horizontal(|ui| {
+                    let (label, total) = match self.remaining_summary {
+                        RemainingSummary::Sum => ("剩余总和", sum),
+                        RemainingSummary::Max => ("全部完成还需", max),
+                    };
+                    ui.label(format!("{}: {}", label, format_hms(total, self.compact_time)));
+                    let mut changed = ui
+                        .selectable_value(&mut self.remaining_summary, RemainingSummary::Sum, "总和")
+                        .on_hover_text("所有任务剩余时间相加")
+                        .changed();
+                    changed |= ui
+                        .selectable_value(&mut self.remaining_summary, RemainingSummary::Max, "最长")
+                        .on_hover_text("任务同时进行，最长的剩余时间即全部完成的时间")
+                        .changed();
+                    if changed {
+                        self.save_data();
+                    }
+                });
+            }
+
+            ui.horizontal(|ui| {
+                let status = self.status_line();
+                if ui.button("复制").on_hover_text("复制状态文字").clicked() {
+                    ui.output_mut(|o| o.copied_text = status.clone());
+                }
+                ui.add(
+                    TextEdit::singleline(&mut status.as_str())
+                        .desired_width(f32::INFINITY)
+                        .frame(false),
+                );
+            });
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("文字颜色:");
+                let mut color = {
+                    let [r, g, b, _a] = self.text_color.to_array();
+                    [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0]
+                };
+                if ui.color_edit_button_rgb(&mut color).changed() {
+                    self.text_color = Color32::from_rgb(
+                        (color[0] * 255.0) as u8,
+                        (color[1] * 255.0) as u8,
+                        (color[2] * 255.0) as u8,
+                    );
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui
+                    .checkbox(&mut self.compact_time, "紧凑时间格式")
+                    .on_hover_text("不足一小时时显示为 5:03，而不是 00:05:03")
+                    .changed()
+                {
+                    self.save_data();
+                }
+                if ui
+                    .checkbox(&mut self.progress_color, "进度渐变色")
+                    .on_hover_text("任务卡片边框和进度条随进度由绿变红")
+                    .changed()
+                {
+                    self.save_data();
+                }
+                if ui
+                    .checkbox(&mut self.colorblind, "色盲友好")
+                    .on_hover_text("状态颜色改用蓝、橙配色，不靠红绿区分，错误提示前另加 ⚠")
+                    .changed()
+                {
+                    self.save_data();
+                }
+                if ui
+                    .checkbox(&mut self.clock_minutes_only, "时钟只显示到分钟")
+                    .on_hover_text("没有进行中的任务时每分钟才刷新一次，更省电")
+                    .changed()
+                {
+                    self.save_data();
+                }
+                if ui.checkbox(&mut self.show_date, "显示日期").changed() {
+                    self.save_data();
+                }
+                if ui
+                    .checkbox(&mut self.keep_running_on_close, "关闭窗口时最小化并继续计时")
+                    .changed()
+                {
+                    self.save_data();
+                }
+                if self.keep_running_on_close && ui.button("退出").clicked() {
+                    self.quit_requested = true;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("窗口标题:");
+                let response = ui.add(
+                    TextEdit::singleline(&mut self.app_title).hint_text(DEFAULT_APP_TITLE),
+                );
+                if response.changed() {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("进度提醒:");
+                for pct in MILESTONE_CHOICES {
+                    let mut enabled = self.milestones.contains(&pct);
+                    if ui.checkbox(&mut enabled, format!("{}%", pct)).changed() {
+                        if enabled {
+                            self.milestones.push(pct);
+                            self.milestones.sort_unstable();
+                        } else {
+                            self.milestones.retain(|&p| p != pct);
+                        }
+                        self.save_data();
+                    }
+                }
+                ui.separator();
+                if ui
+                    .checkbox(&mut self.halfway_chime, "过半提示音")
+                    .on_hover_text("任务进行到一半时轻响一声")
+                    .changed()
+                {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let mut changed = ui
+                    .checkbox(&mut self.hour_chime, "整点报时")
+                    .on_hover_text("整点按钟点数敲响，静音时不报时")
+                    .changed();
+                changed |= ui
+                    .add_enabled(self.hour_chime, egui::Checkbox::new(&mut self.half_hour_chime, "半点也报时"))
+                    .changed();
+                ui.separator();
+                changed |= ui
+                    .checkbox(&mut self.add_feedback, "添加确认音")
+                    .on_hover_text("添加任务时轻响一声并短暂高亮新任务")
+                    .changed();
+                if changed {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("自动滚动到:");
+                let mut changed = ui.checkbox(&mut self.scroll_on_add, "新添加的任务").changed();
+                changed |= ui
+                    .checkbox(&mut self.scroll_on_near_finish, "即将结束的任务")
+                    .on_hover_text(format!("剩余 {} 秒时滚动一次", NEAR_FINISH.as_secs()))
+                    .changed();
+                if changed {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("历史保留数量:");
+                let response = ui
+                    .add(DragValue::new(&mut self.history_limit).clamp_range(0..=10000).suffix(" 条"))
+                    .on_hover_text("超出后自动删除最早的记录，0 表示不限");
+                if response.changed() {
+                    self.trim_history();
+                    self.save_data();
+                }
+                ui.label(format!("（当前 {} 条）", self.history.len()));
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("重启后补发提醒:");
+                let response = ui
+                    .add(
+                        DragValue::new(&mut self.realert_window_minutes)
+                            .clamp_range(0..=1440)
+                            .suffix(" 分钟内"),
+                    )
+                    .on_hover_text("程序在提醒未确认时被关闭，下次启动会再提醒一次；0 表示不补发");
+                if response.changed() {
+                    self.save_data();
+                }
+            });
+
+            #[cfg(feature = "idle-detection")]
+            if self.idle_supported {
+                ui.horizontal(|ui| {
+                    let mut changed = ui.checkbox(&mut self.idle_pause, "空闲时自动暂停").changed();
+                    ui.label("空闲阈值:");
+                    let mut minutes = self.idle_threshold_secs / 60;
+                    if ui
+                        .add(DragValue::new(&mut minutes).clamp_range(1..=120).suffix(" 分钟"))
+                        .changed()
+                    {
+                        self.idle_threshold_secs = minutes * 60;
+                        changed = true;
+                    }
+                    if changed {
+                        self.save_data();
+                    }
+                });
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("显示器:");
+                if ui
+                    .button("固定到当前显示器")
+                    .on_hover_text("下次启动时窗口出现在当前所在的显示器和位置")
+                    .clicked()
+                {
+                    self.pin_monitor_requested = true;
+                }
+                if self.pinned_window_pos.is_some() && ui.button("取消固定").clicked() {
+                    self.pinned_window_pos = None;
+                    self.pinned_monitor_size = None;
+                    self.pinned_monitor_scale = None;
+                    self.monitor_status = None;
+                    self.save_data();
+                }
+                if let Some(status) = &self.monitor_status {
+                    ui.label(status);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("提醒音量:");
+                let mut changed = ui
+                    .add(Slider::new(&mut self.alarm_volume, 0.0..=1.0).show_value(false))
+                    .on_hover_text("快捷键 Ctrl+↑ / Ctrl+↓")
+                    .changed();
+                ui.label(format!("{:.0}%", self.alarm_volume * 100.0));
+                changed |= ui
+                    .checkbox(&mut self.muted, "静音")
+                    .on_hover_text("快捷键 M")
+                    .changed();
+                if changed {
+                    self.apply_volume();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(format!("正在播放的声音: {}", self.active_sinks.len()))
+                    .on_hover_text("诊断用：没有提醒在响时应为 0");
+                if ui.add_enabled(!self.active_sinks.is_empty(), Button::new("停止所有声音")).clicked() {
+                    self.stop_all_sounds();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("任务列表位置:");
+                let mut changed = ui.selectable_value(&mut self.task_layout, TaskLayout::Center, "居中").changed();
+                changed |= ui
+                    .selectable_value(&mut self.task_layout, TaskLayout::Side, "侧栏")
+                    .on_hover_text("放在右侧可拖动调宽的侧栏，适合宽屏")
+                    .changed();
+                if changed {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("稍后提醒间隔:");
+                if ui
+                    .add(DragValue::new(&mut self.snooze_minutes).clamp_range(1..=120).suffix(" 分钟"))
+                    .changed()
+                {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("自动移除前保留:");
+                if ui
+                    .add(DragValue::new(&mut self.auto_remove_secs).clamp_range(0..=600).suffix(" 秒"))
+                    .on_hover_text("勾选了“自动移除”的任务结束后，在列表中保留多久")
+                    .changed()
+                {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("单个任务文件:");
+                ui.add(TextEdit::singleline(&mut self.task_file_path).hint_text("task_<编号>.json"));
+                if ui.button("导入").clicked() {
+                    self.import_task();
+                }
+                if let Some(status) = &self.task_file_status {
+                    ui.label(status);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("从其他配置导入历史:");
+                ui.add(TextEdit::singleline(&mut self.history_merge_path).hint_text("countdown_data.json 的路径"));
+                if ui.button("导入").clicked() {
+                    self.merge_history_from();
+                }
+                if let Some(status) = &self.history_merge_status {
+                    ui.label(status);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("提醒最长时间:");
+                let response = ui
+                    .add(DragValue::new(&mut self.alarm_max_secs).clamp_range(0..=3600).suffix(" 秒"))
+                    .on_hover_text("超过该时间仍在响的提醒音会自动停止，0 表示不限");
+                if response.changed() {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("延长按钮:");
+                let response = ui
+                    .add(TextEdit::singleline(&mut self.extend_minutes_input).desired_width(100.0))
+                    .on_hover_text("逗号分隔的分钟数，例如 1,5,10");
+                if response.lost_focus() {
+                    self.extend_minutes = self
+                        .extend_minutes_input
+                        .split([',', '，'])
+                        .filter_map(|m| m.trim().parse().ok())
+                        .filter(|&m| m > 0)
+                        .collect();
+                    // 去掉无效的部分，显示实际生效的按钮
+                    self.extend_minutes_input = join_minutes(&self.extend_minutes);
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("默认任务名:");
+                let response = ui
+                    .add(TextEdit::singleline(&mut self.auto_names_input).hint_text("例如 喝水,休息,伸展"))
+                    .on_hover_text("任务名留空时依次轮流使用，留空则为“任务#编号”");
+                if response.lost_focus() {
+                    let names: Vec<String> = self
+                        .auto_names_input
+                        .split([',', '，'])
+                        .map(|n| n.trim().to_string())
+                        .filter(|n| !n.is_empty())
+                        .collect();
+                    if names != self.auto_names {
+                        self.auto_names = names;
+                        self.auto_name_index = 0;
+                    }
+                    self.auto_names_input = self.auto_names.join(",");
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("提醒音样式:");
+                let mut changed = false;
+                for (name, pattern) in ALARM_PATTERNS {
+                    let selected = !self.alarm_pattern_custom && self.alarm_pattern == pattern;
+                    if ui.selectable_label(selected, name).clicked() {
+                        self.alarm_pattern = pattern;
+                        self.alarm_pattern_custom = false;
+                        changed = true;
+                    }
+                }
+                if ui.selectable_label(self.alarm_pattern_custom, "自定义").clicked() {
+                    self.alarm_pattern_custom = true;
+                }
+                if self.alarm_pattern_custom {
+                    let pattern = &mut self.alarm_pattern;
+                    changed |= ui
+                        .add(DragValue::new(&mut pattern.repeat).clamp_range(1..=10).suffix(" 次"))
+                        .changed();
+                    ui.label("间隔");
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut pattern.interval_secs)
+                                .clamp_range(0.0..=30.0)
+                                .speed(0.1)
+                                .suffix(" 秒"),
+                        )
+                        .changed();
+                    ui.label("渐强");
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut pattern.fade_in_secs)
+                                .clamp_range(0.0..=10.0)
+                                .speed(0.1)
+                                .suffix(" 秒"),
+                        )
+                        .changed();
+                }
+                if ui.small_button("试听").clicked() {
+                    self.play_alarm_sound();
+                }
+                if changed {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let mut changed = ui
+                    .checkbox(&mut self.finish_hook_enabled, "结束时执行命令:")
+                    .on_hover_text("命令以当前用户身份运行，只填写你信任的命令；任务 JSON 从标准输入传入")
+                    .changed();
+                changed |= ui
+                    .add_enabled(
+                        self.finish_hook_enabled,
+                        TextEdit::singleline(&mut self.finish_hook_command).hint_text("例如：notify.sh"),
+                    )
+                    .changed();
+                if changed {
+                    self.finish_hook_status = None;
+                    self.save_data();
+                }
+            });
+            if let Some(status) = &self.finish_hook_status {
+                error_label(ui, self.palette(), status);
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("主题:");
+                let mut changed = false;
+                for mode in ThemeMode::ALL {
+                    changed |= ui.selectable_value(&mut self.theme_mode, mode, mode.label()).changed();
+                }
+                if self.theme_mode == ThemeMode::Auto {
+                    ui.label("浅色");
+                    changed |= ui
+                        .add(DragValue::new(&mut self.theme_day_start_hour).clamp_range(0..=23).suffix(" 点"))
+                        .changed();
+                    ui.label("至");
+                    changed |= ui
+                        .add(DragValue::new(&mut self.theme_night_start_hour).clamp_range(0..=23).suffix(" 点"))
+                        .changed();
+                }
+                if changed {
+                    self.save_data();
+                }
+            });
+
+            if ui
+                .checkbox(&mut self.show_background, "显示背景")
+                .on_hover_text("关闭后使用纯色背景，不删除背景图片")
+                .changed()
+            {
+                if !self.show_background {
+                    // 释放纹理，重新打开时由 load_background 重新加载
+                    self.background_texture = None;
+                }
+                self.save_data();
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("背景铺放:").on_hover_text("快捷键 B 循环切换");
+                let mut changed = false;
+                for fit in BackgroundFit::ALL {
+                    changed |= ui.selectable_value(&mut self.background_fit, fit, fit.label()).changed();
+                }
+                if changed {
+                    self.save_data();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("背景文件夹:");
+                ui.add(TextEdit::singleline(&mut self.background_dir).hint_text("留空使用单张背景"));
+                ui.checkbox(&mut self.background_random, "启动时随机")
+                    .on_hover_text("关闭时使用按文件名排序的第一张");
+                if ui.button("应用").clicked() {
+                    self.background_dir_choice = None;
+                    self.background_dir_status = None;
+                    self.background_texture = None;
+                    self.save_data();
+                }
+            });
+            if let Some(status) = &self.background_dir_status {
+                ui.label(status);
+            }
+
+            CollapsingHeader::new("直播叠加层").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .checkbox(&mut self.overlay_mode, "叠加层模式")
+                        .on_hover_text("无边框置顶，只显示进行中的计时；右键退出。透明背景需重启后生效")
+                        .changed()
+                    {
+                        self.save_data();
+                    }
+                    ui.label("背景:");
+                    if ui.color_edit_button_srgba(&mut self.overlay_background).changed() {
+                        self.save_data();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("状态图片:");
+                    let mut changed = ui
+                        .add(
+                            TextEdit::singleline(&mut self.status_image_path)
+                                .hint_text("例如 timers.png，留空不写"),
+                        )
+                        .changed();
+                    ui.label("每");
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut self.status_image_interval_secs)
+                                .clamp_range(1..=60)
+                                .suffix(" 秒"),
+                        )
+                        .changed();
+                    if changed {
+                        self.status_image_status = None;
+                        self.save_data();
+                    }
+                });
+                if let Some(status) = &self.status_image_status {
+                    error_label(ui, self.palette(), status);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let mut changed = ui
+                    .checkbox(&mut self.status_text_enabled, "状态文件:")
+                    .on_hover_text("把最快结束的任务及剩余时间写入文本文件，没有进行中的任务或关闭时删除该文件")
+                    .changed();
+                changed |= ui
+                    .add(TextEdit::singleline(&mut self.status_text_path).hint_text("例如 countdown.txt"))
+                    .changed();
+                if changed {
+                    self.status_text_dirty = true;
+                    self.status_text_status = None;
+                    self.save_data();
+                }
+            });
+            if let Some(status) = &self.status_text_status {
+                error_label(ui, self.palette(), status);
+            }
+
+            #[cfg(feature = "lan-sync")]
+            {
+                ui.horizontal(|ui| {
+                    ui.label("局域网同步:");
+                    ui.selectable_value(&mut self.sync_mode, SyncMode::Off, "关闭");
+                    ui.selectable_value(&mut self.sync_mode, SyncMode::Host, "主机");
+                    ui.selectable_value(&mut self.sync_mode, SyncMode::Mirror, "镜像");
+                    match self.sync_mode {
+                        SyncMode::Off => {}
+                        SyncMode::Host => {
+                            ui.label("发送到:");
+                            ui.add(TextEdit::singleline(&mut self.sync_target).desired_width(160.0));
+                        }
+                        SyncMode::Mirror => {
+                            ui.label("监听:");
+                            ui.add(TextEdit::singleline(&mut self.sync_bind).desired_width(160.0));
+                        }
+                    }
+                    if ui.button("应用").clicked() {
+                        self.apply_sync();
                         self.save_data();
-                        self.show_finished_popup = Some(task.id);
                     }
                 });
+                if let Some(status) = &self.sync_status {
+                    error_label(ui, self.palette(), status);
+                }
+            }
+
+            #[cfg(feature = "background-url")]
+            {
+                ui.horizontal(|ui| {
+                    ui.label("背景图片 URL:");
+                    ui.text_edit_singleline(&mut self.background_url_input);
+                    let downloading = self.background_download.is_some();
+                    if ui.add_enabled(!downloading, Button::new("加载")).clicked() {
+                        self.start_background_download();
+                    }
+                });
+                if let Some(status) = &self.background_status {
+                    ui.label(status);
+                }
+            }
+
+            ui.separator();
+
+            // 改为垂直布局，避免按钮被挤出窗口
+            ui.group(|ui| {
+                ui.label("任务名:");
+                ui.text_edit_singleline(&mut self.new_task_name);
+                ui.add_space(4.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("倒计时 (秒或 HH:MM:SS):")
+                        .on_hover_text("快捷键 N 可直接跳到此输入框");
+                    let help = ui.small_button("?").on_hover_text("支持的时长格式");
+                    let popup_id = ui.make_persistent_id("duration_format_help");
+                    if help.clicked() {
+                        ui.memory_mut(|m| m.toggle_popup(popup_id));
+                    }
+                    popup_below_widget(ui, popup_id, &help, |ui| {
+                        ui.set_min_width(200.0);
+                        ui.label(RichText::new("支持的时长格式").strong());
+                        Grid::new("duration_format_help_grid").show(ui, |ui| {
+                            for (format, example) in DURATION_FORMAT_HELP {
+                                ui.label(*format);
+                                ui.label(*example);
+                                ui.end_row();
+                            }
+                        });
+                        ui.label("分、秒超过 59 会自动进位，如 1:70 为 2分10秒");
+                    });
+                });
+                let duration_response = ui
+                    .text_edit_singleline(&mut self.new_task_input)
+                    .on_hover_text("按回车直接添加");
+                if focus_duration_input {
+                    duration_response.request_focus();
+                }
+                // 单行输入框按回车会失去焦点，所以本帧有焦点或刚失去焦点都算在输入框中按下
+                let in_field = duration_response.has_focus() || duration_response.lost_focus();
+                let (enter_pressed, enter_held) = ui.input(|i| (i.key_pressed(Key::Enter), i.key_down(Key::Enter)));
+                let mut add_requested = self.add_enter_guard.fire(in_field && enter_pressed, enter_held);
+                if duration_response.changed() {
+                    self.add_error = None;
+                    self.confirm_long_add = false;
+                }
+                ui.add_space(4.0);
+
+                ui.label("完成语 (可选):");
+                ui.add(
+                    TextEdit::singleline(&mut self.new_task_finish_text)
+                        .hint_text("例如：面包烤好了！"),
+                );
+                ui.label("备注 (可选):");
+                ui.add(
+                    TextEdit::multiline(&mut self.new_task_note)
+                        .hint_text("例如：180 度，中途翻面")
+                        .desired_rows(2),
+                );
+                ui.horizontal(|ui| {
+                    ui.label("结束时:");
+                    ui.checkbox(&mut self.new_task_sound, "响铃");
+                    ui.checkbox(&mut self.new_task_notify, "系统通知");
+                    ui.checkbox(&mut self.new_task_overtime, "继续计超时")
+                        .on_hover_text("到时后以红色显示超出了多久，确认或删除时记入历史记录");
+                    ui.checkbox(&mut self.new_task_auto_remove, "自动移除")
+                        .on_hover_text(format!("结束 {} 秒后自动确认并移到历史记录", self.auto_remove_secs));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("提前提醒:");
+                    ui.add(
+                        TextEdit::singleline(&mut self.new_task_lead_input)
+                            .hint_text("例如 5:00, 1:00，留空不提醒")
+                            .desired_width(140.0),
+                    )
+                    .on_hover_text("剩余时间到达每一项时轻响一声、卡片闪烁并发送通知，多项用逗号分隔");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("标签:");
+                    ui.add(
+                        TextEdit::singleline(&mut self.new_task_tags)
+                            .hint_text("例如 工作, 厨房")
+                            .desired_width(140.0),
+                    )
+                    .on_hover_text("多个标签用逗号或空格分隔，可在任务列表和历史记录上方按标签筛选");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("定时开始:");
+                    ui.add(
+                        TextEdit::singleline(&mut self.new_task_start_input)
+                            .hint_text("例如 15:00，留空立即开始")
+                            .desired_width(140.0),
+                    )
+                    .on_hover_text("到这个时间才开始倒计时，之前在列表中显示为等待");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("背景音:");
+                    ambient_picker(ui, "new_task_ambient", &mut self.new_task_ambient);
+                });
+                if let Some(status) = &self.ambient_status {
+                    error_label(ui, self.palette(), status);
+                }
+                ui.add_space(4.0);
+
+                ui.horizontal(|ui| {
+                    // 同一帧里回车和按钮都触发时也只添加一次
+                    add_requested |= ui.button("添加").clicked();
+                    if add_requested {
+                        self.add_task_from_form(false);
+                    }
+                    if ui
+                        .button("⏱ 开始秒表")
+                        .on_hover_text("从零开始正计时，不需要填写时长")
+                        .clicked()
+                    {
+                        self.start_stopwatch();
+                    }
+                    if self.confirm_long_add {
+                        if ui.button("仍然添加").clicked() {
+                            self.add_task_from_form(true);
+                        }
+                        if ui.button("取消").clicked() {
+                            self.add_error = None;
+                            self.confirm_long_add = false;
+                        }
+                    }
+                });
+                if let Some(error) = &self.add_error {
+                    error_label(ui, self.palette(), error);
+                }
+
+                if let Some(last) = &self.last_finished {
+                    let label = format!("再来一次：{}（{}）", last.name, last.input);
+                    if ui.button(label).clicked() {
+                        self.repeat_last_finished();
+                    }
+                }
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("快捷预设:");
+                    let mut start_index = None;
+                    for (index, preset) in self.presets.iter().enumerate() {
+                        let mut button = Button::new(&preset.name);
+                        if let Some(color) = preset.color {
+                            button = button.stroke(Stroke::new(2.0, color_from_array(color)));
+                        }
+                        let hover = if preset.alert_sound {
+                            preset.input.clone()
+                        } else {
+                            format!("{}，结束时不响铃", preset.input)
+                        };
+                        if ui.add(button).on_hover_text(hover).clicked() {
+                            start_index = Some(index);
+                        }
+                    }
+                    if let Some(index) = start_index {
+                        self.start_preset(index);
+                    }
+                    if ui
+                        .small_button("＋ 存为预设")
+                        .on_hover_text("用上面的任务名和时长新建预设")
+                        .clicked()
+                    {
+                        self.save_form_as_preset();
+                    }
+                });
+                if let Some(status) = &self.preset_status {
+                    error_label(ui, self.palette(), status);
+                }
+                self.refresh_frequent();
+                if !self.frequent.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("常用:").on_hover_text("根据历史记录中完成次数最多的任务自动推荐");
+                        let mut start_index = None;
+                        for (index, frequent) in self.frequent.iter().enumerate() {
+                            let label = format!("{}（{}）", frequent.name, frequent.input);
+                            let hover = format!("已完成 {} 次", frequent.count);
+                            if ui.button(label).on_hover_text(hover).clicked() {
+                                start_index = Some(index);
+                            }
+                        }
+                        if let Some(index) = start_index {
+                            self.start_frequent(index);
+                        }
+                    });
+                }
+                CollapsingHeader::new("编辑预设").show(ui, |ui| {
+                    self.show_preset_editor(ui);
+                });
+            });
+
+            CollapsingHeader::new("特别倒计时").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("目标时间:");
+                    let response = ui.add(
+                        TextEdit::singleline(&mut self.special_target)
+                            .hint_text("留空为午夜，或 2027-01-01 00:00"),
+                    );
+                    if response.changed() {
+                        self.save_data();
+                    }
+                    if ui.button("开始").clicked() {
+                        self.start_special_countdown();
+                    }
+                });
+                if let Some(status) = &self.special_status {
+                    error_label(ui, self.palette(), status);
+                }
+            });
+
+            CollapsingHeader::new("闹钟").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("名称:");
+                    ui.add(TextEdit::singleline(&mut self.alarm_name).hint_text("可选").desired_width(100.0));
+                    ui.label("时间:");
+                    let response = ui.add(
+                        TextEdit::singleline(&mut self.alarm_input)
+                            .hint_text("14:30 或 2027-07-01 09:00")
+                            .desired_width(140.0),
+                    );
+                    let enter = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                    if ui
+                        .button("设定")
+                        .on_hover_text("只写时刻时为今天，已经过去则为明天")
+                        .clicked()
+                        || enter
+                    {
+                        self.start_alarm();
+                    }
+                });
+                if let Some(status) = &self.alarm_status {
+                    error_label(ui, self.palette(), status);
+                }
+            });
+
+            CollapsingHeader::new("连续倒计时").show(ui, |ui| {
+                self.show_chain_builder(ui);
+            });
+
+            CollapsingHeader::new("番茄钟").show(ui, |ui| {
+                self.show_pomodoro_settings(ui);
+            });
+
+            CollapsingHeader::new("重复计时").show(ui, |ui| {
+                self.show_recurring(ui);
+            });
+
+            CollapsingHeader::new("棋钟").show(ui, |ui| {
+                self.show_chess_clock(ui);
+            });
+
+            CollapsingHeader::new("计划").show(ui, |ui| {
+                self.show_planned(ui);
             });
 
             ui.separator();
 
-            ui.heading("历史记录");
+            #[cfg(feature = "lan-sync")]
+            self.show_mirror_tasks(ui);
+
+            let (task_list_height, history_height) = match self.task_layout {
+                TaskLayout::Center => {
+                    let task_list_height = LIST_AREA_HEIGHT * self.list_split;
+                    (task_list_height, LIST_AREA_HEIGHT - task_list_height)
+                }
+                TaskLayout::Side => (0.0, LIST_AREA_HEIGHT),
+            };
+            let mut export_request = None;
+            let mut restart_request = None;
+
+            if self.task_layout == TaskLayout::Center {
+                self.show_task_list(ui, task_list_height);
+                self.show_split_handle(ui);
+            }
+
+            ui.horizontal(|ui| {
+                ui.heading("历史记录");
+                let unacknowledged = self.unacknowledged_count();
+                if unacknowledged > 0 {
+                    let badge = RichText::new(format!("{} 个未确认", unacknowledged))
+                        .color(Color32::WHITE)
+                        .background_color(self.palette().alert);
+                    if ui
+                        .small_button(badge)
+                        .on_hover_text("点击确认全部已结束的任务（重要任务需在弹窗中单独确认）")
+                        .clicked()
+                    {
+                        let finished: Vec<usize> = self
+                            .tasks
+                            .iter()
+                            .filter(|t| t.finished_at.is_some() && !t.important)
+                            .map(|t| t.id)
+                            .collect();
+                        for id in finished {
+                            self.acknowledge_task(id);
+                        }
+                    }
+                    if ui
+                        .small_button("全部稍后")
+                        .on_hover_text(format!("{} 分钟后再次提醒所有已结束的任务", self.snooze_minutes))
+                        .clicked()
+                    {
+                        self.snooze_all();
+                    }
+                }
+            });
+
+            let history_tags = collect_tags(&self.history);
+            if self.history_tag_filter.as_ref().is_some_and(|tag| !history_tags.contains(tag)) {
+                self.history_tag_filter = None;
+            }
+            if !history_tags.is_empty() {
+                tag_filter_bar(ui, &history_tags, &mut self.history_tag_filter);
+            }
+            let history_filter = self.history_tag_filter.clone();
+            let history_shown = |t: &CountdownTask| history_filter.as_ref().is_none_or(|tag| t.tags.contains(tag));
+
+            if !self.history.is_empty() {
+                ui.horizontal(|ui| {
+                    if ui.toggle_value(&mut self.history_select_mode, "多选").changed() {
+                        self.history_selected.clear();
+                    }
+                    if self.history_select_mode {
+                        if ui.button("全选").clicked() {
+                            self.history_selected = self.history.iter().filter(|t| history_shown(t)).map(|t| t.id).collect();
+                        }
+                        if ui.button("全不选").clicked() {
+                            self.history_selected.clear();
+                        }
+                        let count = self.history_selected.len();
+                        if ui
+                            .add_enabled(count > 0, Button::new(format!("删除所选 ({})", count)))
+                            .clicked()
+                        {
+                            if count > BULK_DELETE_CONFIRM {
+                                self.confirm_history_delete = true;
+                            } else {
+                                self.delete_selected_history();
+                            }
+                        }
+                    }
+                });
+            }
 
             ui.push_id("history_list", |ui| {
-                ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                ScrollArea::vertical().max_height(history_height).show(ui, |ui| {
                     if self.history.is_empty() {
                         ui.label("暂无历史记录");
                     }
                     let mut remove_history_ids = Vec::new();
-                    for task in self.history.iter().rev() {
-                        ui.horizontal(|ui| {
+                    let mut replay = None;
+                    let palette = self.palette();
+                    for task in self.history.iter().rev().filter(|t| history_shown(t)) {
+                        // 按第一个标签给整行加浅色底，先占位，行画完后再填入
+                        let background = ui.painter().add(egui::Shape::Noop);
+                        let row = ui.horizontal(|ui| {
+                            if self.history_select_mode {
+                                let mut checked = self.history_selected.contains(&task.id);
+                                if ui.checkbox(&mut checked, "").changed() {
+                                    if checked {
+                                        self.history_selected.insert(task.id);
+                                    } else {
+                                        self.history_selected.remove(&task.id);
+                                    }
+                                }
+                            }
+                            let length_label = match task.kind {
+                                TaskKind::Countdown => "设定时长",
+                                TaskKind::Stopwatch => "秒表用时",
+                                TaskKind::Alarm { .. } => "响铃时间",
+                            };
                             ui.label(format!(
-                                "任务名: {}，开始时间: {}，设定时长: {}",
+                                "任务名: {}，开始时间: {}，{}: {}，来源: {}",
                                 task.name,
                                 task.created_at.format("%Y-%m-%d %H:%M:%S"),
-                                task.input
+                                length_label,
+                                task.input,
+                                task.source.label()
                             ));
+                            for tag in &task.tags {
+                                ui.label(RichText::new(format!("#{}", tag)).small().color(tag_color(tag)));
+                            }
+                            if !task.extended.is_zero() {
+                                ui.label(format!("含延长 {}", format_hms(task.extended, self.compact_time)))
+                                    .on_hover_text("运行中用“+N分”追加的时长，设定时长已包含这部分");
+                            }
+                            if task.snooze_count > 0 {
+                                let original = task
+                                    .snoozed_from
+                                    .and_then(|id| self.history.iter().find(|t| t.id == id));
+                                let response = ui.label(format!("第 {} 次稍后提醒", task.snooze_count));
+                                if let Some(original) = original {
+                                    response.on_hover_text(format!(
+                                        "原任务开始于 {}，设定时长 {}",
+                                        original.created_at.format("%Y-%m-%d %H:%M:%S"),
+                                        original.input
+                                    ));
+                                }
+                            }
+                            if let Some(overtime) = task.overtime {
+                                ui.colored_label(
+                                    palette.error,
+                                    format!("超时 {}", format_hms(overtime, self.compact_time)),
+                                );
+                            }
+                            if !task.note.is_empty() {
+                                // 有结果时直接显示结果，完整备注放在悬停提示里
+                                let label = match task.outcome() {
+                                    Some(outcome) => RichText::new(format!("{}{}", OUTCOME_PREFIX, outcome)).italics(),
+                                    None => RichText::new("📄"),
+                                };
+                                ui.label(label).on_hover_text(&task.note);
+                            }
+                            Self::show_overrun_badge(ui, task, self.compact_time, palette);
+                            if task.alert_sound
+                                && ui
+                                    .small_button("▶")
+                                    .on_hover_text("重放这个任务结束时响起的提醒音")
+                                    .clicked()
+                            {
+                                replay = Some((task.important, task.alarm_pattern));
+                            }
+                            if Self::can_restart(task)
+                                && ui.button("重新开始").on_hover_text("以相同的任务名和时长新建").clicked()
+                            {
+                                restart_request = Some(task.clone());
+                            }
+                            if ui.button("导出").clicked() {
+                                export_request = Some(task.clone());
+                            }
                             if ui.button("删除").clicked() {
                                 remove_history_ids.push(task.id);
                             }
                         });
+                        if let Some(tag) = task.tags.first() {
+                            let tint = tag_color(tag).gamma_multiply(0.15);
+                            ui.painter()
+                                .set(background, egui::Shape::rect_filled(row.response.rect.expand(2.0), 3.0, tint));
+                        }
                         ui.add_space(4.0);
                     }
+                    match replay {
+                        Some((true, _)) => self.play_important_alarm(),
+                        Some((false, pattern)) => self.play_alarm_pattern(pattern.unwrap_or(self.alarm_pattern)),
+                        None => {}
+                    }
                     if !remove_history_ids.is_empty() {
-                        self.history.retain(|t| !remove_history_ids.contains(&t.id));
+                        self.delete_history(&remove_history_ids);
+                    }
+                });
+            });
+
+            if let Some(task) = export_request {
+                self.export_task(&task);
+            }
+            if let Some(task) = restart_request {
+                self.restart_task(&task, TaskSource::Repeat);
+            }
+
+            ui.separator();
+
+            CollapsingHeader::new("统计").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("每日专注目标:");
+                    let response = ui
+                        .add(
+                            DragValue::new(&mut self.focus_goal_minutes)
+                                .clamp_range(0..=24 * 60)
+                                .speed(5)
+                                .suffix(" 分钟"),
+                        )
+                        .on_hover_text("按今天结束的任务设定时长累计，0 表示不设目标");
+                    if response.changed() {
                         self.save_data();
                     }
                 });
+                if self.focus_goal_minutes > 0 {
+                    let done = self.focus_time_today();
+                    let goal = Duration::from_secs(self.focus_goal_minutes * 60);
+                    let fraction = (done.as_secs_f32() / goal.as_secs_f32()).min(1.0);
+                    ui.add(ProgressBar::new(fraction).text(format!(
+                        "{} / {}",
+                        format_duration_words(done),
+                        format_duration_words(goal)
+                    )));
+                }
+                ui.add_space(4.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("每日完成数:");
+                    for days in [7, 14, 30] {
+                        ui.selectable_value(&mut self.stats_days, days, format!("近{}天", days));
+                    }
+                });
+                self.show_completion_chart(ui);
+                ui.horizontal(|ui| {
+                    ui.add(TextEdit::singleline(&mut self.stats_export_path).desired_width(160.0));
+                    if ui
+                        .button("导出统计图")
+                        .on_hover_text("将每日完成数柱状图和下面的过去一年热力图保存为一张 SVG 图片")
+                        .clicked()
+                    {
+                        self.export_stats_image();
+                    }
+                });
+                if let Some(status) = &self.stats_export_status {
+                    ui.label(status);
+                }
+
+                ui.add_space(8.0);
+                ui.label("过去一年:");
+                self.show_activity_heatmap(ui);
+
+                CollapsingHeader::new("重复某一天").show(ui, |ui| {
+                    let counts = self.daily_completion_counts(self.stats_days);
+                    let mut any = false;
+                    for (date, _) in counts.into_iter().rev() {
+                        let count = self.history_on_day(date).len();
+                        if count == 0 {
+                            continue;
+                        }
+                        any = true;
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}  {} 个任务", date.format("%Y-%m-%d"), count));
+                            if ui.small_button("重复这一天").clicked() {
+                                self.repeat_day_request = Some(date);
+                            }
+                        });
+                    }
+                    if !any {
+                        ui.label("这段时间内没有可重复的任务");
+                    }
+                });
             });
         });
 
+        if let Some(date) = self.repeat_day_request {
+            let tasks = self.history_on_day(date);
+            let names: Vec<String> = tasks
+                .iter()
+                .map(|t| format!("{}（{}）", t.name, t.input))
+                .collect();
+            Window::new("重复这一天")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "将重新开始 {} 完成的 {} 个任务：",
+                        date.format("%Y-%m-%d"),
+                        names.len()
+                    ));
+                    ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        for name in &names {
+                            ui.label(name);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("全部开始").clicked() {
+                            self.repeat_day(date);
+                            self.repeat_day_request = None;
+                        }
+                        if ui.button("取消").clicked() {
+                            self.repeat_day_request = None;
+                        }
+                    });
+                });
+        }
+
         if let Some(id) = self.show_finished_popup {
             Window::new("提醒")
                 .collapsible(false)
                 .resizable(false)
                 .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ctx, |ui| {
-                    let task_time = self
-                        .tasks
-                        .iter()
-                        .find(|t| t.id == id)
-                        .map(|t| t.created_at.format("%Y-%m-%d %H:%M:%S").to_string())
-                        .unwrap_or_else(|| "未知".to_string());
-                    let task_name = self
-                        .tasks
-                        .iter()
-                        .find(|t| t.id == id)
-                        .map(|t| t.name.clone())
-                        .unwrap_or_else(|| "未知任务".to_string());
-                    ui.label(format!("任务“{}”开始于 {} 的倒计时已结束！", task_name, task_time));
-                    if ui.button("关闭").clicked() {
-                        self.show_finished_popup = None;
+                    let task = self.tasks.iter().find(|t| t.id == id);
+                    let important = task.is_some_and(|t| t.important);
+                    let message = task
+                        .map(|t| t.finish_message())
+                        .unwrap_or_else(|| "任务“未知任务”的倒计时已结束".to_string());
+                    if important {
+                        ui.label(RichText::new("⭐ 重要任务").strong().color(self.palette().alert));
                     }
+                    ui.label(format!("{}！", message.trim_end_matches('！')));
+                    if self.finish_note_id != Some(id) {
+                        // 提醒被另一个任务的提醒替换时，先把已填的结果记到原来的任务上
+                        if let Some(old) = self.finish_note_id {
+                            self.save_finish_note(old);
+                        }
+                        self.finish_note.clear();
+                        self.finish_note_id = Some(id);
+                    }
+                    ui.add(
+                        TextEdit::singleline(&mut self.finish_note)
+                            .hint_text("结果（可选），记在任务备注末尾，例如：构建成功"),
+                    );
+                    ui.horizontal(|ui| {
+                        if self.last_finished.as_ref().is_some_and(|t| t.id == id)
+                            && ui.button("再来一次").clicked()
+                        {
+                            self.save_finish_note(id);
+                            self.repeat_last_finished();
+                            self.acknowledge_task(id);
+                            self.show_finished_popup = None;
+                        }
+                        if ui.button(format!("再响 {} 分钟", self.snooze_minutes)).clicked() {
+                            self.save_finish_note(id);
+                            self.snooze_task(id);
+                            self.stop_all_sounds();
+                        }
+                        if ui.button(if important { "确认" } else { "关闭" }).clicked() {
+                            self.save_finish_note(id);
+                            self.acknowledge_task(id);
+                            self.show_finished_popup = None;
+                        }
+                        if important {
+                            return;
+                        }
+                        let unacknowledged = self.unacknowledged_count();
+                        let label = if unacknowledged > 1 {
+                            format!("全部稍后（{} 个）", unacknowledged)
+                        } else {
+                            "全部稍后".to_string()
+                        };
+                        if ui
+                            .button(label)
+                            .on_hover_text(format!("{} 分钟后再次提醒", self.snooze_minutes))
+                            .clicked()
+                        {
+                            self.save_finish_note(id);
+                            self.snooze_all();
+                        }
+                    });
+                });
+        }
+
+        let palette = self.palette();
+        if let Some(edit) = &mut self.task_edit {
+            let countdown = self
+                .tasks
+                .iter()
+                .any(|t| t.id == edit.id && t.kind == TaskKind::Countdown);
+            let (mut apply, mut cancel) = (false, false);
+            Window::new("编辑任务")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("任务名:");
+                        ui.text_edit_singleline(&mut edit.name);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("标签:");
+                        ui.text_edit_singleline(&mut edit.tags);
+                    });
+                    if countdown {
+                        ui.horizontal(|ui| {
+                            ui.label("设定时长:");
+                            ui.text_edit_singleline(&mut edit.input)
+                                .on_hover_text("已经过去的时间保持不变，剩余时间按新时长重算");
+                        });
+                    }
+                    if let Some(error) = &edit.error {
+                        error_label(ui, palette, error);
+                    }
+                    ui.horizontal(|ui| {
+                        apply = ui.button("保存").clicked();
+                        cancel = ui.button("取消").clicked();
+                    });
+                });
+            if apply {
+                self.apply_task_edit();
+            } else if cancel {
+                self.task_edit = None;
+            }
+        }
+
+        if let Some(preset) = self.preset_overwrite.clone() {
+            Window::new("覆盖预设")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!("已有名为“{}”的预设，是否覆盖？", preset.name));
+                    ui.horizontal(|ui| {
+                        if ui.button("覆盖").clicked() {
+                            self.overwrite_preset(preset);
+                            self.preset_overwrite = None;
+                        }
+                        if ui.button("取消").clicked() {
+                            self.preset_overwrite = None;
+                        }
+                    });
+                });
+        }
+
+        if self.confirm_history_delete {
+            Window::new("删除历史记录")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!("确定删除所选的 {} 条历史记录？此操作无法撤销。", self.history_selected.len()));
+                    ui.horizontal(|ui| {
+                        if ui.button("删除").clicked() {
+                            self.delete_selected_history();
+                        }
+                        if ui.button("取消").clicked() {
+                            self.confirm_history_delete = false;
+                        }
+                    });
+                });
+        }
+
+        if let Some(chain) = self.chain_overwrite.clone() {
+            Window::new("覆盖连续倒计时")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!("已有名为“{}”的连续倒计时，是否覆盖？", chain.name));
+                    ui.horizontal(|ui| {
+                        if ui.button("覆盖").clicked() {
+                            self.overwrite_chain(chain);
+                            self.chain_overwrite = None;
+                        }
+                        if ui.button("取消").clicked() {
+                            self.chain_overwrite = None;
+                        }
+                    });
                 });
         }
 
-        ctx.request_repaint_after(Duration::from_millis(200));
+        if let Some(shown) = self.undo_toast {
+            if shown.elapsed() < UNDO_TOAST
+                && let Some(entry) = self.undo_stack.last()
+            {
+                let text = entry.describe();
+                let mut undo = false;
+                Area::new("undo_toast")
+                    .anchor(Align2::CENTER_BOTTOM, [0.0, -80.0])
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(text);
+                                undo = ui.button("撤销").on_hover_text("Ctrl+Z").clicked();
+                            });
+                        });
+                    });
+                if undo {
+                    self.undo_delete();
+                }
+            } else {
+                self.undo_toast = None;
+            }
+        }
+
+        if let Some(shown) = self.background_fit_toast {
+            if shown.elapsed() < Duration::from_millis(1500) {
+                Area::new("background_fit_toast")
+                    .anchor(Align2::CENTER_BOTTOM, [0.0, -40.0])
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.label(format!("背景: {}", self.background_fit.label()));
+                        });
+                    });
+            } else {
+                self.background_fit_toast = None;
+            }
+        }
+
+        if std::mem::take(&mut self.overlay_drag_requested) {
+            frame.drag_window();
+        }
+
+        ctx.request_repaint_after(self.repaint_interval());
+    }
+
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        if self.overlay_mode {
+            self.overlay_background.to_normalized_gamma_f32()
+        } else {
+            // 与 eframe 的默认值相同
+            Color32::from_rgba_unmultiplied(12, 12, 12, 180).to_normalized_gamma_f32()
+        }
+    }
+
+    fn on_close_event(&mut self) -> bool {
+        // 没有托盘图标时隐藏窗口会留下无法找回的进程，因此改为最小化
+        if self.keep_running_on_close && !self.quit_requested {
+            self.minimize_requested = true;
+            return false;
+        }
+        true
     }
 }
 
 fn main() {
-    let native_options = eframe::NativeOptions::default();
+    let mut native_options = eframe::NativeOptions::default();
+
+    let mut app = ClockApp::default();
+    // 优先级：已保存的数据 > 命令行参数 > config.toml > 内置默认值
+    let args = config::parse_args(std::env::args().skip(1)).unwrap_or_else(|e| {
+        app.config_warning = Some(format!("{}，已忽略命令行参数", e));
+        config::Args {
+            config_path: config::CONFIG_PATH.to_string(),
+            overrides: config::Config::default(),
+        }
+    });
+    let file = match config::load(&args.config_path) {
+        Ok(config) => config.unwrap_or_default(),
+        Err(e) => {
+            app.config_warning = Some(format!("{}，已使用内置默认设置", e));
+            config::Config::default()
+        }
+    };
+    app.apply_config(file.override_with(args.overrides));
+    app.load_data();
+    #[cfg(feature = "lan-sync")]
+    app.apply_sync();
+    if let Some([x, y]) = app.pinned_window_pos {
+        native_options.initial_window_pos = Some(egui::pos2(x, y));
+    }
+    native_options.fullscreen = app.fullscreen;
+    // 透明窗口只能在创建时指定，切换叠加层模式后需重启才有透明背景
+    native_options.transparent = app.overlay_mode;
+    native_options.decorated = !app.overlay_mode;
+    native_options.always_on_top = app.overlay_mode;
+    let title = app.window_title();
+    app.applied_title = title.clone();
 
-    eframe::run_native(
-        "Rust 多任务倒计时",
+    let _ = eframe::run_native(
+        &title,
         native_options,
-        Box::new(|cc| {
+        Box::new(move |cc| {
             let mut fonts = egui::FontDefinitions::default();
             fonts.font_data.insert(
                 "fz_font".to_owned(),
-                egui::FontData::from_static(CUSTOM_FONT_DATA).into(),
+                egui::FontData::from_static(CUSTOM_FONT_DATA),
             );
             fonts
                 .families
@@ -457,9 +7236,414 @@ fn main() {
                 .insert(0, "fz_font".to_owned());
             cc.egui_ctx.set_fonts(fonts);
 
-            let mut app = ClockApp::default();
-            app.load_data();
             Box::new(app)
         }),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(input: &str) -> Option<u64> {
+        ClockApp::parse_duration(input).map(|d| d.as_secs())
+    }
+
+    #[test]
+    fn parse_duration_seconds() {
+        assert_eq!(secs("90"), Some(90));
+        assert_eq!(secs("0"), Some(0));
+    }
+
+    #[test]
+    fn parse_duration_minutes_seconds() {
+        assert_eq!(secs("1:30"), Some(90));
+        assert_eq!(secs("0:05"), Some(5));
+    }
+
+    #[test]
+    fn parse_duration_hours_minutes_seconds() {
+        assert_eq!(secs("1:02:03"), Some(3723));
+    }
+
+    #[test]
+    fn parse_duration_trims_surrounding_whitespace() {
+        assert_eq!(secs("  1:30\n"), Some(90));
+        assert_eq!(secs("1: 30"), None);
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid_input() {
+        assert_eq!(secs(""), None);
+        assert_eq!(secs("   "), None);
+        assert_eq!(secs("a:b"), None);
+        assert_eq!(secs("-5"), None);
+        assert_eq!(secs("1:2:3:4"), None);
+    }
+
+    #[test]
+    fn parse_duration_normalizes_overflowing_fields() {
+        assert_eq!(secs("1:70"), Some(130));
+        assert_eq!(secs("0:90:00"), Some(5400));
+    }
+
+    #[test]
+    fn parse_duration_rejects_arithmetic_overflow() {
+        assert_eq!(secs("18446744073709551615:00"), None);
+        assert_eq!(secs("9999999999999999999:00:00"), None);
+        // 超过一年的时长视为输入错误
+        assert_eq!(secs("999999:00:00"), None);
+        assert_eq!(secs("8784:00:00"), Some(8784 * 3600));
+        assert_eq!(secs("8785:00:00"), None);
+    }
+
+    #[test]
+    fn parse_duration_unit_suffixes() {
+        assert_eq!(secs("5m"), Some(300));
+        assert_eq!(secs("1h30m"), Some(5400));
+        assert_eq!(secs("45S"), Some(45));
+        assert_eq!(secs("5x"), None);
+        assert_eq!(secs("m"), None);
+    }
+
+    #[test]
+    fn parse_duration_expressions() {
+        assert_eq!(secs("5m+30s"), Some(330));
+        assert_eq!(secs("1h-10m"), Some(3000));
+        assert_eq!(secs("1:00 + 30"), Some(90));
+        assert_eq!(secs("10m-10m"), Some(0));
+    }
+
+    #[test]
+    fn parse_duration_rejects_malformed_expressions() {
+        assert_eq!(secs("5m++"), None);
+        assert_eq!(secs("5m+"), None);
+        assert_eq!(secs("+5m"), None);
+        assert_eq!(secs("1m-2m"), None);
+    }
+
+    #[test]
+    fn lead_reminders_sorted_and_checked_against_duration() {
+        let dur = Duration::from_secs(600);
+        assert_eq!(ClockApp::parse_lead_reminders("1:00, 5:00，1m", dur), Ok(vec![300, 60]));
+        assert_eq!(ClockApp::parse_lead_reminders("  ", dur), Ok(Vec::new()));
+        assert!(ClockApp::parse_lead_reminders("10:00", dur).is_err());
+        assert!(ClockApp::parse_lead_reminders("1:00, abc", dur).is_err());
+    }
+
+    #[test]
+    fn enter_guard_fires_once_while_held() {
+        let mut guard = EnterGuard::default();
+        // 第一帧按下，之后两帧按住产生自动重复
+        assert!(guard.fire(true, true));
+        assert!(!guard.fire(true, true));
+        assert!(!guard.fire(false, true));
+        // 松开后再按一次才再次触发
+        assert!(!guard.fire(false, false));
+        assert!(guard.fire(true, false));
+    }
+
+    #[test]
+    fn enter_guard_press_and_release_in_one_frame() {
+        let mut guard = EnterGuard::default();
+        assert!(guard.fire(true, false));
+        assert!(!guard.fire(false, false));
+        assert!(guard.fire(true, false));
+    }
+
+    #[test]
+    fn format_hms_full() {
+        assert_eq!(format_hms(Duration::from_secs(90), false), "00:01:30");
+        assert_eq!(format_hms(Duration::from_secs(2 * 3600), false), "02:00:00");
+    }
+
+    #[test]
+    fn format_days_hms_adds_days() {
+        assert_eq!(format_days_hms(Duration::from_secs(90), true), "1:30");
+        assert_eq!(
+            format_days_hms(Duration::from_secs(3 * 86400 + 5 * 3600 + 61), true),
+            "3天 05:01:01"
+        );
+    }
+
+    #[test]
+    fn format_hms_compact() {
+        assert_eq!(format_hms(Duration::from_secs(90), true), "1:30");
+        assert_eq!(format_hms(Duration::from_secs(2 * 3600), true), "2:00:00");
+    }
+
+    #[test]
+    fn stopwatch_counts_up_and_never_finishes() {
+        let mut task = CountdownTask::stopwatch(1, "跑步".to_string());
+        task.elapsed_before_pause = Duration::from_secs(90);
+        task.paused = true;
+        assert!(!task.is_finished());
+        assert_eq!(task.progress(), 0.0);
+        assert_eq!(task.shown_time(), Duration::from_secs(90));
+    }
+
+    #[test]
+    fn parse_tags_splits_and_dedups() {
+        assert_eq!(parse_tags(" 工作，厨房, 工作  健身、"), ["工作", "厨房", "健身"]);
+        assert!(parse_tags("  ").is_empty());
+        assert!(tag_color("厨房") == tag_color("厨房"));
+    }
+
+    #[test]
+    fn task_without_priority_field_loads_as_normal() {
+        let task = CountdownTask::new(1, "汇报".to_string(), "5:00".to_string(), Duration::from_secs(300));
+        let mut json = serde_json::to_value(&task).unwrap();
+        // 加入优先级之前保存的数据：只有重要星标，没有 priority
+        json["important"] = serde_json::Value::Bool(true);
+        json.as_object_mut().unwrap().remove("priority");
+        let loaded: CountdownTask = serde_json::from_value(json.clone()).unwrap();
+        assert!(loaded.important);
+        assert!(loaded.priority == Priority::Normal);
+
+        json["priority"] = serde_json::Value::String("Low".to_string());
+        let loaded: CountdownTask = serde_json::from_value(json).unwrap();
+        assert!(loaded.important && loaded.priority == Priority::Low);
+    }
+
+    #[test]
+    fn set_duration_keeps_elapsed_time() {
+        let mut task = CountdownTask::new(1, "会议".to_string(), "10:00".to_string(), Duration::from_secs(600));
+        task.elapsed_before_pause = Duration::from_secs(240);
+        task.paused = true;
+        task.lead_reminders = vec![180];
+        task.lead_reminded = vec![180];
+        task.extend(Duration::from_secs(60));
+        task.set_duration(Duration::from_secs(480), "8:00".to_string());
+        assert_eq!(task.remaining(), Duration::from_secs(240));
+        assert_eq!(task.progress(), 0.5);
+        assert!(task.extended.is_zero());
+        // 剩余时间又长于提前量，提前提醒重新生效
+        assert!(task.lead_reminded.is_empty());
+    }
+
+    #[test]
+    fn outcome_is_appended_to_note_and_not_inherited() {
+        let mut task = CountdownTask::new(1, "构建".to_string(), "10:00".to_string(), Duration::from_secs(600));
+        task.note = "release 分支".to_string();
+        task.add_outcome("构建成功");
+        assert_eq!(task.note, "release 分支\n结果: 构建成功");
+        assert_eq!(task.outcome(), Some("构建成功"));
+
+        let mut again = CountdownTask::new(2, "构建".to_string(), "10:00".to_string(), Duration::from_secs(600));
+        again.inherit_options(&task);
+        assert_eq!(again.note, "release 分支");
+        assert_eq!(again.outcome(), None);
+    }
+
+    #[test]
+    fn pomodoro_phases_alternate_and_end_after_long_break() {
+        let settings = PomodoroSettings {
+            cycles: 2,
+            ..PomodoroSettings::default()
+        };
+        let mut link = PomodoroLink {
+            settings,
+            cycle: 1,
+            on_break: false,
+        };
+        let mut phases = vec![(link.cycle, link.on_break, link.minutes())];
+        while let Some(next) = link.next() {
+            link = next;
+            phases.push((link.cycle, link.on_break, link.minutes()));
+        }
+        assert_eq!(phases, [(1, false, 25), (1, true, 5), (2, false, 25), (2, true, 15)]);
+
+        // 短休息为 0 时跳过休息直接进入下一个番茄
+        let mut link = PomodoroLink {
+            settings: PomodoroSettings {
+                break_minutes: 0,
+                ..settings
+            },
+            cycle: 1,
+            on_break: false,
+        };
+        let mut phases = vec![(link.cycle, link.on_break, link.minutes())];
+        while let Some(next) = link.next() {
+            link = next;
+            phases.push((link.cycle, link.on_break, link.minutes()));
+        }
+        assert_eq!(phases, [(1, false, 25), (2, false, 25), (2, true, 15)]);
+    }
+
+    #[test]
+    fn chain_link_skips_cancelled_steps_in_one_round_only() {
+        let step = |name: &str| ChainStep {
+            name: name.to_string(),
+            input: "0:40".to_string(),
+            color: None,
+        };
+        let mut link = ChainLink::new(CountdownChain {
+            name: "间歇".to_string(),
+            steps: vec![step("练"), step("歇")],
+            rounds: 3,
+            quiet_transitions: false,
+        });
+        link.skipped.push((1, 1));
+        assert_eq!(link.upcoming().collect::<Vec<_>>(), [(0, 1), (1, 0), (2, 0), (2, 1)]);
+        let mut visited = vec![(link.round, link.index)];
+        while let Some(next) = link.next() {
+            link = next;
+            visited.push((link.round, link.index));
+        }
+        assert_eq!(visited, [(0, 0), (0, 1), (1, 0), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn moving_a_task_keeps_the_others_in_their_sections() {
+        let header = |position| TaskHeader {
+            title: "下午".to_string(),
+            position,
+        };
+        // 移动后按标题分成上下两段
+        let move_task = |tasks: &mut Vec<&str>, headers: &mut Vec<TaskHeader>, from, to| {
+            let task = tasks.remove(from);
+            tasks.insert(to, task);
+            ClockApp::shift_headers_on_move(headers, from, to);
+            let p = headers[0].position;
+            (tasks[..p].concat(), tasks[p..].concat())
+        };
+
+        // A | 标题 | B C：A 拖到最后，B C 仍在标题下面
+        let (mut tasks, mut headers) = (vec!["A", "B", "C"], vec![header(1)]);
+        assert_eq!(move_task(&mut tasks, &mut headers, 0, 2), ("".to_string(), "BCA".to_string()));
+
+        // A B | 标题 | C：C 拖到最前，A B 仍在标题上面
+        let (mut tasks, mut headers) = (vec!["A", "B", "C"], vec![header(2)]);
+        assert_eq!(move_task(&mut tasks, &mut headers, 2, 0), ("CAB".to_string(), "".to_string()));
+    }
+
+    #[test]
+    fn undoing_delete_of_last_task_in_section_keeps_it_above_next_header() {
+        let task = |id, name: &str| CountdownTask::new(id, name.to_string(), "5:00".to_string(), Duration::from_secs(300));
+        // A B | 标题 | C
+        let mut tasks = vec![task(0, "A"), task(1, "B"), task(2, "C")];
+        let mut headers = vec![TaskHeader {
+            title: "下午".to_string(),
+            position: 2,
+        }];
+        let saved = headers.clone();
+        let removed = vec![(1, tasks[1].clone())];
+        ClockApp::collapse_headers(&tasks, &mut headers, &[1]);
+        tasks.remove(1);
+        assert_eq!(headers[0].position, 1);
+
+        ClockApp::reinsert_tasks(&mut tasks, &mut headers, removed, saved);
+        let names: Vec<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["A", "B", "C"]);
+        assert_eq!(headers[0].position, 2);
+    }
+
+    #[test]
+    fn saved_headers_count_only_saved_tasks() {
+        let at = Local::now() + chrono::Duration::hours(1);
+        let countdown = |id| CountdownTask::new(id, "倒计时".to_string(), "5:00".to_string(), Duration::from_secs(300));
+        // 倒计时 闹钟 | 上午 | 倒计时 闹钟 | 下午 |
+        let tasks = vec![
+            countdown(0),
+            CountdownTask::alarm(1, "闹钟".to_string(), at),
+            countdown(2),
+            CountdownTask::alarm(3, "闹钟".to_string(), at),
+        ];
+        let headers = vec![
+            TaskHeader {
+                title: "上午".to_string(),
+                position: 2,
+            },
+            TaskHeader {
+                title: "下午".to_string(),
+                position: 4,
+            },
+        ];
+        let saved: Vec<usize> = ClockApp::headers_for_saving(&tasks, &headers)
+            .iter()
+            .map(|h| h.position)
+            .collect();
+        // 重启后只恢复两个闹钟，标题仍分别在第二个闹钟之前和末尾
+        assert_eq!(saved, [1, 2]);
+    }
+
+    #[test]
+    fn command_line_overrides_config_file() {
+        let file: config::Config = toml::from_str("volume = 0.3\ntheme = \"Light\"").unwrap();
+        let args = ["--volume", "0.8", "--muted"].map(String::from);
+        let args = config::parse_args(args).unwrap();
+        assert_eq!(args.config_path, config::CONFIG_PATH);
+        let merged = file.override_with(args.overrides);
+        assert_eq!(merged.volume, Some(0.8));
+        assert_eq!(merged.muted, Some(true));
+        // 命令行没有给出的设置仍取配置文件的
+        assert!(merged.theme == Some(ThemeMode::Light));
+
+        assert!(config::parse_args(["--volume".to_string()]).is_err());
+        assert!(config::parse_args(["--theme", "sepia"].map(String::from)).is_err());
+        assert!(config::parse_args(["--verbose".to_string()]).is_err());
+    }
+
+    #[test]
+    fn parse_alarm_time_rolls_past_times_to_tomorrow() {
+        let now = Local.with_ymd_and_hms(2026, 3, 10, 15, 0, 0).unwrap();
+        let later = parse_alarm_time("16:30", now).unwrap();
+        assert_eq!(later.naive_local().to_string(), "2026-03-10 16:30:00");
+        let earlier = parse_alarm_time("14:30", now).unwrap();
+        assert_eq!(earlier.naive_local().to_string(), "2026-03-11 14:30:00");
+        let dated = parse_alarm_time("2026-07-01 09:00", now).unwrap();
+        assert_eq!(dated.naive_local().to_string(), "2026-07-01 09:00:00");
+        assert!(parse_alarm_time("2026-01-01 09:00", now).is_err());
+        assert!(parse_alarm_time("9点", now).is_err());
+    }
+
+    #[test]
+    fn repeat_rule_weekdays_skips_weekend() {
+        let time = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        // 2026-03-13 是星期五
+        let friday_evening = Local.with_ymd_and_hms(2026, 3, 13, 18, 0, 0).unwrap();
+        let next = RepeatRule::Weekdays { time }.next_after(friday_evening).unwrap();
+        assert_eq!(next.naive_local().to_string(), "2026-03-16 08:00:00");
+        let next = RepeatRule::Daily { time }.next_after(friday_evening).unwrap();
+        assert_eq!(next.naive_local().to_string(), "2026-03-14 08:00:00");
+        assert!(RepeatRule::Every { minutes: 5 }.next_after(friday_evening).is_none());
+    }
+
+    #[test]
+    fn chess_clock_increments() {
+        use chess_clock::{ChessClock, ChessSettings, Increment};
+
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        for (increment, expected) in [(Increment::Fischer, 300 - 2 + 5), (Increment::Bronstein, 300)] {
+            let mut clock = ChessClock::new(ChessSettings {
+                base_minutes: 5,
+                increment_secs: 5,
+                increment,
+            });
+            // 黑方按钟，白方开始走时；白方 2 秒后走完
+            clock.press(1, at(0));
+            assert_eq!(clock.active(), Some(0));
+            clock.press(0, at(2));
+            assert_eq!(clock.active(), Some(1));
+            assert_eq!(clock.remaining(0, at(2)), Duration::from_secs(expected));
+        }
+    }
+
+    #[test]
+    fn chess_clock_flags_when_time_runs_out() {
+        use chess_clock::{ChessClock, ChessSettings, Increment};
+
+        let start = Instant::now();
+        let mut clock = ChessClock::new(ChessSettings {
+            base_minutes: 1,
+            increment_secs: 0,
+            increment: Increment::None,
+        });
+        clock.press(0, start);
+        assert_eq!(clock.tick(start + Duration::from_secs(59)), None);
+        assert_eq!(clock.tick(start + Duration::from_secs(60)), Some(1));
+        assert_eq!(clock.flagged, Some(1));
+        assert_eq!(clock.active(), None);
+    }
+}
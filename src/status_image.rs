@@ -0,0 +1,89 @@
+//! 把进行中的计时渲染成 PNG，供 OBS 等直播软件的图片/浏览器源读取。
+//! 不依赖窗口，直接用 ab_glyph 把文字画进 RGBA 缓冲区。
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+const PADDING: u32 = 12;
+
+/// 每行一段文字，文字色和背景色均为 RGBA（背景 alpha 为 0 即透明）
+pub fn render(font_data: &[u8], lines: &[String], size: f32, text: [u8; 4], background: [u8; 4]) -> Option<RgbaImage> {
+    let font = FontRef::try_from_slice(font_data).ok()?;
+    let scaled = font.as_scaled(PxScale::from(size));
+    let line_height = scaled.height() + scaled.line_gap();
+
+    let line_width = |line: &str| -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(prev) = previous {
+                width += scaled.kern(prev, id);
+            }
+            width += scaled.h_advance(id);
+            previous = Some(id);
+        }
+        width
+    };
+    let content_width = lines.iter().map(|l| line_width(l)).fold(0.0, f32::max);
+    let width = content_width.ceil() as u32 + PADDING * 2;
+    let height = (line_height * lines.len() as f32).ceil() as u32 + PADDING * 2;
+
+    let mut image = RgbaImage::from_pixel(width, height, Rgba(background));
+    for (row, line) in lines.iter().enumerate() {
+        let baseline = PADDING as f32 + row as f32 * line_height + scaled.ascent();
+        let mut x = PADDING as f32;
+        let mut previous = None;
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(prev) = previous {
+                x += scaled.kern(prev, id);
+            }
+            let glyph = id.with_scale_and_position(scaled.scale(), ab_glyph::point(x, baseline));
+            x += scaled.h_advance(id);
+            previous = Some(id);
+
+            let Some(outline) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px as u32 >= width || py as u32 >= height {
+                    return;
+                }
+                let pixel = image.get_pixel_mut(px as u32, py as u32);
+                pixel.0 = blend_over(text, coverage, pixel.0);
+            });
+        }
+    }
+    Some(image)
+}
+
+/// 按覆盖率把文字色叠加到已有像素上（非预乘 alpha 的 over 运算）
+fn blend_over(src: [u8; 4], coverage: f32, dst: [u8; 4]) -> [u8; 4] {
+    let sa = src[3] as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+    let da = dst[3] as f32 / 255.0;
+    let out_a = sa + da * (1.0 - sa);
+    if out_a <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let mut out = [0; 4];
+    for i in 0..3 {
+        let c = (src[i] as f32 * sa + dst[i] as f32 * da * (1.0 - sa)) / out_a;
+        out[i] = c.round() as u8;
+    }
+    out[3] = (out_a * 255.0).round() as u8;
+    out
+}
+
+/// 先写临时文件再改名，读取方不会读到写了一半的图片
+pub fn save(image: &RgbaImage, path: &str) -> Result<(), String> {
+    let tmp = format!("{}.tmp", path);
+    image
+        .save_with_format(&tmp, image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, Path::new(path)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,141 @@
+//! 局域网同步：主机每秒用 UDP 发送一次全部任务的快照，镜像端只读显示。
+//! 发送快照而不是增量事件，丢包、镜像端中途加入或主机重启后都能在下一次快照追上。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    net::UdpSocket,
+    time::{Duration, Instant},
+};
+
+/// 主机发送快照的间隔
+const SEND_INTERVAL: Duration = Duration::from_secs(1);
+/// 超过该时间没有收到快照，镜像端视为与主机断开
+const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+/// 协议版本，不一致的消息直接忽略
+const PROTOCOL_VERSION: u32 = 1;
+/// 单个 UDP 包的接收缓冲区大小，任务很多时快照会被截断而解析失败
+const MAX_PACKET: usize = 64 * 1024;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SyncTask {
+    pub id: usize,
+    pub name: String,
+    pub duration_ms: u64,
+    pub remaining_ms: u64,
+    pub paused: bool,
+}
+
+impl SyncTask {
+    pub fn finished(&self) -> bool {
+        self.remaining_ms == 0
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SyncMessage {
+    version: u32,
+    host: String,
+    tasks: Vec<SyncTask>,
+}
+
+pub struct Host {
+    socket: UdpSocket,
+    target: String,
+    last_sent: Option<Instant>,
+}
+
+impl Host {
+    /// `target` 可以是广播地址，也可以是某台镜像机器的地址
+    pub fn new(target: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_broadcast(true)?;
+        Ok(Self {
+            socket,
+            target: target.to_string(),
+            last_sent: None,
+        })
+    }
+
+    /// 距上次发送超过间隔时发送快照；`tasks` 只在需要发送时才计算
+    pub fn send_if_due(&mut self, host: &str, tasks: impl FnOnce() -> Vec<SyncTask>) -> io::Result<()> {
+        if self.last_sent.is_some_and(|t| t.elapsed() < SEND_INTERVAL) {
+            return Ok(());
+        }
+        self.last_sent = Some(Instant::now());
+        let message = SyncMessage {
+            version: PROTOCOL_VERSION,
+            host: host.to_string(),
+            tasks: tasks(),
+        };
+        let bytes = serde_json::to_vec(&message).map_err(io::Error::other)?;
+        self.socket.send_to(&bytes, &self.target)?;
+        Ok(())
+    }
+}
+
+pub struct Mirror {
+    socket: UdpSocket,
+    pub host: Option<String>,
+    pub tasks: Vec<SyncTask>,
+    received_at: Option<Instant>,
+}
+
+impl Mirror {
+    pub fn new(bind: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            host: None,
+            tasks: Vec::new(),
+            received_at: None,
+        })
+    }
+
+    /// 读完所有待处理的包，只保留最新的快照；返回本次新结束的任务
+    pub fn poll(&mut self) -> Vec<SyncTask> {
+        let mut buf = vec![0; MAX_PACKET];
+        let mut latest = None;
+        // 出错（通常是 WouldBlock，表示暂时没有数据）时停止，留到下一帧再读
+        while let Ok((len, _)) = self.socket.recv_from(&mut buf) {
+            if let Ok(message) = serde_json::from_slice::<SyncMessage>(&buf[..len])
+                && message.version == PROTOCOL_VERSION
+            {
+                latest = Some(message);
+            }
+        }
+
+        let Some(message) = latest else {
+            return Vec::new();
+        };
+        let just_finished = message
+            .tasks
+            .iter()
+            .filter(|t| t.finished() && self.tasks.iter().any(|old| old.id == t.id && !old.finished()))
+            .cloned()
+            .collect();
+        self.host = Some(message.host);
+        self.tasks = message.tasks;
+        self.received_at = Some(Instant::now());
+        just_finished
+    }
+
+    /// 距离最近一次收到快照的时间，从未收到时为 `None`
+    pub fn since_last_message(&self) -> Option<Duration> {
+        self.received_at.map(|t| t.elapsed())
+    }
+
+    pub fn connected(&self) -> bool {
+        self.since_last_message().is_some_and(|d| d < PEER_TIMEOUT)
+    }
+
+    /// 按收到快照后经过的时间推算剩余时间，断开后停在最后的值
+    pub fn remaining(&self, task: &SyncTask) -> Duration {
+        let remaining = Duration::from_millis(task.remaining_ms);
+        match self.since_last_message() {
+            Some(elapsed) if !task.paused && self.connected() => remaining.saturating_sub(elapsed),
+            _ => remaining,
+        }
+    }
+}